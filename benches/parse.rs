@@ -0,0 +1,99 @@
+//! Allocation and throughput benchmark for the local JSONL parser
+//!
+//! Run with `cargo bench --bench parse`. Compares `parse_jsonl_file` (one
+//! reused line buffer) against the naive `lines()` loop it replaced, counting
+//! heap allocations with a wrapping global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{BufRead, Cursor};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use claude_token_counter::{parse_jsonl_file, AggregatedUsage, LogEntry, UsageFilter};
+
+/// Counts every allocation made through the system allocator
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Lines in the generated fixture (roughly 80 MB)
+const LINES: usize = 200_000;
+
+/// Build a large log where, like real sessions, most lines carry no usage
+fn large_fixture() -> String {
+    let padding = "x".repeat(400);
+    let mut log = String::new();
+    for i in 0..LINES {
+        if i % 4 == 0 {
+            log.push_str(&format!(
+                r#"{{"type":"assistant","message":{{"model":"claude-sonnet-4-5","usage":{{"input_tokens":{},"output_tokens":20,"cache_creation_input_tokens":30,"cache_read_input_tokens":40}}}},"timestamp":"2025-12-02T10:00:05Z"}}"#,
+                i
+            ));
+        } else {
+            log.push_str(&format!(
+                r#"{{"type":"user","message":{{"role":"user","content":"{}"}},"timestamp":"2025-12-02T10:00:00Z"}}"#,
+                padding
+            ));
+        }
+        log.push('\n');
+    }
+    log
+}
+
+/// The previous implementation: a fresh `String` for every line
+fn parse_with_lines(reader: impl BufRead) -> AggregatedUsage {
+    let mut aggregated = AggregatedUsage::default();
+    for line in reader.lines() {
+        let line = line.unwrap();
+        if line.trim().is_empty() || !line.contains("\"usage\"") {
+            continue;
+        }
+        if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
+            if let Some(usage) = entry.message.as_ref().and_then(|m| m.usage.as_ref()) {
+                aggregated.add(usage);
+            }
+        }
+    }
+    aggregated
+}
+
+/// Run `f`, returning its result, allocation count, and elapsed milliseconds
+fn measure<T>(f: impl FnOnce() -> T) -> (T, usize, u128) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed().as_millis();
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before, elapsed)
+}
+
+fn main() {
+    let log = large_fixture();
+    println!("Fixture: {} lines, {:.1} MB", LINES, log.len() as f64 / 1_000_000.0);
+
+    let (old, old_allocs, old_ms) = measure(|| parse_with_lines(Cursor::new(log.as_bytes())));
+    let (new, new_allocs, new_ms) = measure(|| {
+        parse_jsonl_file(Cursor::new(log.as_bytes()), Path::new("bench.jsonl"), &UsageFilter::default()).unwrap()
+    });
+
+    assert_eq!(old.total(), new.total());
+    assert_eq!(old.message_count, new.message_count);
+
+    println!("lines():      {:>9} allocations {:>6} ms", old_allocs, old_ms);
+    println!("read_line():  {:>9} allocations {:>6} ms", new_allocs, new_ms);
+    println!("Saved {} allocations ({:.1}x fewer)", old_allocs - new_allocs, old_allocs as f64 / new_allocs.max(1) as f64);
+}
@@ -1,29 +1,210 @@
-use anyhow::{Context, Result};
+use chrono::NaiveDate;
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
-use crate::models::UsageResponse;
+use serde::de::DeserializeOwned;
+use tracing::Instrument;
+use std::fmt;
+use std::time::Duration;
+use crate::cache::{self, UsageSnapshot};
+use crate::models::{CostResponse, ReportPage, UsageResponse};
 
 const API_BASE_URL: &str = "https://api.anthropic.com/v1";
 
+/// Largest page size the usage endpoint accepts (also the default)
+pub const MAX_PAGE_LIMIT: u32 = 1000;
+
+/// Daily buckets requested per page of the cost report (the most it allows)
+const COST_PAGE_LIMIT: u32 = 31;
+
+/// Extra attempts at a page after the first when it fails with a retryable error
+const PAGE_RETRIES: u32 = 3;
+
+/// Longest wait between page retries, even when `Retry-After` asks for more
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// Errors returned by the Anthropic API client
+#[derive(Debug)]
+pub enum ApiError {
+    /// The API key contains characters that can't be sent in a header
+    InvalidApiKey,
+    /// The request never got a response (DNS, TLS, connection, timeout)
+    Network(reqwest::Error),
+    /// 401: the key is invalid, expired, or revoked
+    Unauthorized,
+    /// 403: the key is valid but lacks organization admin scope
+    Forbidden,
+    /// 404: the endpoint isn't available for this key
+    NotFound(String),
+    /// 429: too many requests; `retry_after` is in seconds when the API provides it
+    RateLimited { retry_after: Option<u64> },
+    /// 5xx: the API failed on its side
+    Server(u16, String),
+    /// Any other unsuccessful status
+    Http(u16, String),
+    /// The response body wasn't the JSON we expected
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidApiKey => write!(f, "Invalid API key format"),
+            ApiError::Network(e) => write!(f, "Failed to send request to Anthropic API: {}", e),
+            ApiError::Unauthorized => write!(
+                f,
+                "API key is invalid or has been revoked (401).\n\
+                 Create a new Admin key at https://console.anthropic.com/settings/keys \
+                 and run 'config --api-key YOUR_ADMIN_KEY' again"
+            ),
+            ApiError::Forbidden => write!(
+                f,
+                "API key is not allowed to read organization usage (403).\n\
+                 Usage data needs an Admin API key (sk-ant-admin-...) created by an organization admin"
+            ),
+            ApiError::NotFound(body) => write!(
+                f,
+                "API endpoint not found. This likely means:\n\
+                 1. You need an Admin API key (starts with 'sk-ant-admin-...')\n\
+                 2. Regular API keys (sk-ant-api...) don't have access to usage data\n\
+                 3. Get an Admin key from: https://console.anthropic.com/settings/keys\n\n\
+                 Error details: {}",
+                body
+            ),
+            ApiError::RateLimited { retry_after: Some(secs) } => {
+                write!(f, "Rate limited by Anthropic API. Retry after {} seconds", secs)
+            }
+            ApiError::RateLimited { retry_after: None } => {
+                write!(f, "Rate limited by Anthropic API. Try again shortly")
+            }
+            ApiError::Server(status, body) => {
+                write!(f, "Anthropic API server error (status {}): {}", status, body)
+            }
+            ApiError::Http(status, body) => {
+                write!(f, "API request failed with status {}: {}", status, body)
+            }
+            ApiError::Parse(e) => write!(f, "Failed to parse API response as JSON: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Network(e) => Some(e),
+            ApiError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ApiError {
+    /// Whether the same request might succeed if tried again
+    fn is_retryable(&self) -> bool {
+        matches!(self, ApiError::Network(_) | ApiError::RateLimited { .. } | ApiError::Server(..))
+    }
+
+    /// Classify an unsuccessful HTTP response
+    fn from_status(status: reqwest::StatusCode, retry_after: Option<u64>, body: String) -> Self {
+        match status.as_u16() {
+            401 => ApiError::Unauthorized,
+            403 => ApiError::Forbidden,
+            404 => ApiError::NotFound(body),
+            429 => ApiError::RateLimited { retry_after },
+            code @ 500..=599 => ApiError::Server(code, body),
+            code => ApiError::Http(code, body),
+        }
+    }
+}
+
+/// Client for the Anthropic Usage API
 pub struct AnthropicClient {
     client: reqwest::Client,
     api_key: String,
+    /// How long cached responses stay fresh; None disables the cache
+    cache_ttl: Option<Duration>,
+    /// Number of records requested per page
+    page_limit: u32,
+    /// API root that endpoint paths are appended to
+    base_url: String,
+    /// Workspace the usage report is scoped to; None reports the whole organization
+    workspace_id: Option<String>,
+    /// First wait before retrying a failed page, doubling on each further attempt
+    retry_backoff: Duration,
 }
 
 impl AnthropicClient {
     /// Create a new Anthropic API client with the given API key
-    pub fn new(api_key: String) -> Result<Self> {
+    pub fn new(api_key: String) -> anyhow::Result<Self> {
         let client = reqwest::Client::new();
-        Ok(Self { client, api_key })
+        Ok(Self {
+            client,
+            api_key,
+            cache_ttl: Some(cache::DEFAULT_TTL),
+            page_limit: MAX_PAGE_LIMIT,
+            base_url: API_BASE_URL.to_string(),
+            workspace_id: None,
+            retry_backoff: Duration::from_secs(1),
+        })
+    }
+
+    /// Send requests to `base_url` (e.g. a gateway re-hosting the API) instead of
+    /// the public Anthropic API; None keeps the default
+    pub fn with_base_url(mut self, base_url: Option<&str>) -> anyhow::Result<Self> {
+        let Some(base_url) = base_url else {
+            return Ok(self);
+        };
+
+        let url = reqwest::Url::parse(base_url)
+            .map_err(|e| anyhow::anyhow!("Invalid API base URL '{}': {}", base_url, e))?;
+        if !matches!(url.scheme(), "http" | "https") || url.cannot_be_a_base() {
+            anyhow::bail!("Invalid API base URL '{}': expected an http(s) URL", base_url);
+        }
+
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        Ok(self)
+    }
+
+    /// Only report usage from `workspace_id`; None keeps the organization-wide report
+    pub fn with_workspace_id(mut self, workspace_id: Option<&str>) -> anyhow::Result<Self> {
+        let Some(workspace_id) = workspace_id else {
+            return Ok(self);
+        };
+
+        let workspace_id = workspace_id.trim();
+        if workspace_id.is_empty() {
+            anyhow::bail!("Workspace ID must not be empty");
+        }
+        // The ID also ends up in cache file names, so keep it to the characters IDs use
+        if !workspace_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            anyhow::bail!("Invalid workspace ID '{}': expected letters, digits, '_' or '-'", workspace_id);
+        }
+
+        self.workspace_id = Some(workspace_id.to_string());
+        Ok(self)
+    }
+
+    /// Set the number of records requested per page (1 to `MAX_PAGE_LIMIT`)
+    pub fn with_page_limit(mut self, page_limit: u32) -> anyhow::Result<Self> {
+        if !(1..=MAX_PAGE_LIMIT).contains(&page_limit) {
+            anyhow::bail!("Page limit must be between 1 and {}, got {}", MAX_PAGE_LIMIT, page_limit);
+        }
+        self.page_limit = page_limit;
+        Ok(self)
+    }
+
+    /// Set how long cached usage responses stay fresh (None disables caching)
+    pub fn with_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.cache_ttl = ttl;
+        self
     }
 
     /// Build headers for API requests
-    fn build_headers(&self) -> Result<HeaderMap> {
+    fn build_headers(&self) -> Result<HeaderMap, ApiError> {
         let mut headers = HeaderMap::new();
 
         headers.insert(
             "x-api-key",
             HeaderValue::from_str(&self.api_key)
-                .context("Invalid API key format")?,
+                .map_err(|_| ApiError::InvalidApiKey)?,
         );
 
         headers.insert(
@@ -43,63 +224,544 @@ impl AnthropicClient {
     ///
     /// Requires an Admin API key (sk-ant-admin-...)
     /// Uses the Claude Code Analytics API endpoint
-    /// Note: This endpoint returns data for ONE day at a time
+    /// Queries from `days_back` days ago up to now (0 means today only,
+    /// since data appears within 5 minutes)
+    ///
+    /// Returns a cached response for the same query when it is still fresh
     pub async fn fetch_usage(
         &self,
         days_back: u32,
-    ) -> Result<UsageResponse> {
-        // Query today's date (since data appears within 5 minutes)
-        let target_date = chrono::Utc::now();
+    ) -> Result<UsageResponse, ApiError> {
+        let target_date = chrono::Utc::now() - chrono::Duration::days(i64::from(days_back));
+        let starting_at = target_date.format("%Y-%m-%d").to_string();
+
+        self.fetch(&starting_at, None)
+            .instrument(tracing::debug_span!("fetch_usage", days_back))
+            .await
+    }
+
+    /// Fetch the usage report from `days_back` days ago as the API sent it, one body per page
+    ///
+    /// Skips the cache and `UsageResponse` entirely, so it still works when the API adds
+    /// fields we can't parse; only `has_more` and `next_page` are read to follow pages
+    pub async fn fetch_usage_raw(&self, days_back: u32) -> Result<Vec<String>, ApiError> {
+        let target_date = chrono::Utc::now() - chrono::Duration::days(i64::from(days_back));
         let starting_at = target_date.format("%Y-%m-%d").to_string();
+        let page_limit = self.page_limit.to_string();
+
+        let url = format!("{}/organizations/usage_report/claude_code", self.base_url);
+        let mut query = vec![
+            ("starting_at", starting_at.as_str()),
+            ("limit", page_limit.as_str()),
+        ];
+        if let Some(workspace_id) = &self.workspace_id {
+            query.push(("workspace_ids[]", workspace_id.as_str()));
+        }
+
+        let mut bodies = Vec::new();
+        let mut page: Option<String> = None;
+        loop {
+            let body = self.fetch_page_text(&url, &query, page.as_deref()).await?;
+            let next_page = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .filter(|value| value["has_more"].as_bool() == Some(true))
+                .and_then(|value| value["next_page"].as_str().map(str::to_string));
+            bodies.push(body);
+
+            match next_page {
+                Some(next) => page = Some(next),
+                None => return Ok(bodies),
+            }
+        }
+    }
+
+    /// Fetch usage data for the date range `[starting_at, ending_at)`
+    pub async fn fetch_usage_range(
+        &self,
+        starting_at: NaiveDate,
+        ending_at: NaiveDate,
+    ) -> Result<UsageResponse, ApiError> {
+        let starting_at = starting_at.format("%Y-%m-%d").to_string();
+        let ending_at = ending_at.format("%Y-%m-%d").to_string();
+
+        self.fetch(&starting_at, Some(&ending_at)).await
+    }
+
+    /// Name of the cached response for a query: the date range, plus whatever else
+    /// changes the answer (page size, workspace, the key's organization, and the endpoint)
+    fn cache_key(&self, starting_at: &str, ending_at: Option<&str>) -> String {
+        let mut cache_key = match ending_at {
+            Some(ending_at) => format!("{}_{}", starting_at, ending_at),
+            None => starting_at.to_string(),
+        };
+        if self.page_limit != MAX_PAGE_LIMIT {
+            cache_key.push_str(&format!("_limit{}", self.page_limit));
+        }
+        cache_key.push('_');
+        cache_key.push_str(&self.scope());
+        cache_key
+    }
+
+    /// Whose usage this client sees: its workspace, API key and base URL, safe to use in file names
+    pub fn scope(&self) -> String {
+        let mut scope = String::new();
+        // Keep each workspace's responses apart from each other and from the org-wide report
+        if let Some(workspace_id) = &self.workspace_id {
+            scope.push_str(&format!("ws-{}_", workspace_id));
+        }
+        // Profiles with different keys may be different orgs; the key itself never hits disk
+        scope.push_str(&format!("key-{}", cache::short_hash(&self.api_key)));
+        // A gateway (--api-base-url) may answer differently from the API itself
+        if self.base_url != API_BASE_URL {
+            scope.push_str(&format!("_url-{}", cache::short_hash(&self.base_url)));
+        }
+        scope
+    }
+
+    /// Query the usage endpoint, going through the on-disk cache when enabled
+    async fn fetch(
+        &self,
+        starting_at: &str,
+        ending_at: Option<&str>,
+    ) -> Result<UsageResponse, ApiError> {
+        let cache_key = self.cache_key(starting_at, ending_at);
+        let page_limit = self.page_limit.to_string();
+
+        if let Some(ttl) = self.cache_ttl {
+            if let Some(snapshot) = UsageSnapshot::load_fresh(&cache_key, ttl) {
+                tracing::debug!(key = %cache_key, fetched_at = %snapshot.fetched_at, "using cached response");
+                return Ok(snapshot.response);
+            }
+        }
+
+        let url = format!("{}/organizations/usage_report/claude_code", self.base_url);
+
+        let mut query = vec![
+            ("starting_at", starting_at),
+            ("limit", page_limit.as_str()),
+        ];
+        if let Some(ending_at) = ending_at {
+            query.push(("ending_at", ending_at));
+        }
+        if let Some(workspace_id) = &self.workspace_id {
+            query.push(("workspace_ids[]", workspace_id.as_str()));
+        }
+
+        let usage_response: UsageResponse = self.fetch_all("usage report", &url, &query).await?;
+        tracing::debug!(records = usage_response.data.len(), partial = usage_response.partial, "fetched usage report");
+
+        // Partial results aren't cached, so the next run tries for the full set again
+        if self.cache_ttl.is_some() && !usage_response.partial {
+            let snapshot = UsageSnapshot::new(usage_response);
+            if let Err(e) = snapshot.save(&cache_key) {
+                tracing::warn!("Failed to cache usage response: {}", e);
+            }
+            return Ok(snapshot.response);
+        }
+
+        Ok(usage_response)
+    }
+
+    /// Fetch billed costs from `days_back` days ago up to now from the cost report
+    ///
+    /// Unlike the usage report this is what Anthropic actually charged, across every
+    /// product (not just Claude Code). Never cached, since the point is the current bill
+    pub async fn fetch_cost(&self, days_back: u32) -> Result<CostResponse, ApiError> {
+        let target_date = chrono::Utc::now() - chrono::Duration::days(i64::from(days_back));
+        let starting_at = target_date.format("%Y-%m-%dT00:00:00Z").to_string();
+        let page_limit = COST_PAGE_LIMIT.to_string();
+
+        let url = format!("{}/organizations/cost_report", self.base_url);
+        let mut query = vec![
+            ("starting_at", starting_at.as_str()),
+            ("limit", page_limit.as_str()),
+        ];
+        // The cost report can't be filtered by workspace, only grouped, so group and keep ours
+        if self.workspace_id.is_some() {
+            query.push(("group_by[]", "workspace_id"));
+        }
+
+        let mut cost_response: CostResponse = self.fetch_all("cost report", &url, &query).await?;
+        if let Some(workspace_id) = &self.workspace_id {
+            for record in &mut cost_response.data {
+                record.results.retain(|result| result.workspace_id.as_deref() == Some(workspace_id.as_str()));
+            }
+        }
+
+        Ok(cost_response)
+    }
+
+    /// Fetch every page of a report
+    ///
+    /// A failed first page fails the fetch; later pages are retried, and if one
+    /// still fails the pages collected so far are returned marked partial
+    async fn fetch_all<T: DeserializeOwned>(
+        &self,
+        report: &str,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<ReportPage<T>, ApiError> {
+        let mut response: ReportPage<T> = self.fetch_page(url, query, None).await?;
+        let mut pages = 1;
+        while response.has_more {
+            let Some(page) = response.next_page.take() else {
+                break;
+            };
+
+            match self.fetch_page_with_retries(url, query, &page).await {
+                Ok(next) => {
+                    response.data.extend(next.data);
+                    response.has_more = next.has_more;
+                    response.next_page = next.next_page;
+                    pages += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Showing partial data: page {} of the {} failed ({}); only the first {} page(s) are included",
+                        pages + 1,
+                        report,
+                        e,
+                        pages
+                    );
+                    response.has_more = false;
+                    response.partial = true;
+                    break;
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Fetch one page after the first, retrying network, rate-limit, and server errors
+    /// with exponential backoff (or the `Retry-After` period, when given)
+    async fn fetch_page_with_retries<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        page: &str,
+    ) -> Result<ReportPage<T>, ApiError> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch_page(url, query, Some(page)).await {
+                Err(e) if e.is_retryable() && attempt < PAGE_RETRIES => {
+                    let wait = match e {
+                        ApiError::RateLimited { retry_after: Some(secs) } => Duration::from_secs(secs),
+                        _ => self.retry_backoff * 2u32.pow(attempt),
+                    };
+                    tracing::info!(page, attempt = attempt + 1, error = %e, "retrying page in {:?}", wait.min(MAX_RETRY_WAIT));
+                    tokio::time::sleep(wait.min(MAX_RETRY_WAIT)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 
-        let url = format!("{}/organizations/usage_report/claude_code", API_BASE_URL);
+    /// Request a single page of a report (`page` is the previous page's `next_page`)
+    async fn fetch_page<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        page: Option<&str>,
+    ) -> Result<ReportPage<T>, ApiError> {
+        let response_text = self.fetch_page_text(url, query, page).await?;
+        serde_json::from_str(&response_text).map_err(ApiError::Parse)
+    }
 
-        let request = self.client
-            .get(&url)
+    /// Request a single page and return its body unparsed
+    async fn fetch_page_text(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        page: Option<&str>,
+    ) -> Result<String, ApiError> {
+        let mut request = self.client
+            .get(url)
             .headers(self.build_headers()?)
-            .query(&[
-                ("starting_at", starting_at.as_str()),
-                ("limit", "1000"), // Max limit to get all records
-            ]);
+            .query(query);
+        if let Some(page) = page {
+            request = request.query(&[("page", page)]);
+        }
 
+        tracing::debug!(url, page, "requesting page");
         let response = request
             .send()
             .await
-            .context("Failed to send request to Anthropic API")?;
+            .map_err(ApiError::Network)?;
+        tracing::debug!(status = %response.status(), "response received");
 
         // Check for HTTP errors
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok());
             let body = response.text().await.unwrap_or_default();
 
-            // Provide helpful error messages
-            if status.as_u16() == 404 {
-                anyhow::bail!(
-                    "API endpoint not found. This likely means:\n\
-                     1. You need an Admin API key (starts with 'sk-ant-admin-...')\n\
-                     2. Regular API keys (sk-ant-api...) don't have access to usage data\n\
-                     3. Get an Admin key from: https://console.anthropic.com/settings/keys\n\n\
-                     Error details: {}", body
-                );
-            }
-
-            anyhow::bail!(
-                "API request failed with status {}: {}",
-                status,
-                body
-            );
+            return Err(ApiError::from_status(status, retry_after, body));
         }
 
-        // Get response text for debugging
-        let response_text = response.text().await?;
+        response.text().await.map_err(ApiError::Network)
+    }
+}
 
-        // Print for debugging
-        eprintln!("Claude Code API Response:\n{}", response_text);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use wiremock::matchers::{header, method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
-        // Parse the JSON response
-        let usage_response: UsageResponse = serde_json::from_str(&response_text)
-            .context("Failed to parse API response as JSON")?;
+    const USAGE_BODY: &str = r#"{
+        "data": [{
+            "starting_at": "2025-01-06T00:00:00Z",
+            "ending_at": "2025-01-07T00:00:00Z",
+            "results": [{"input_tokens": 100, "output_tokens": 20, "cache_creation_input_tokens": 5, "cache_read_input_tokens": 40}]
+        }],
+        "has_more": false
+    }"#;
 
-        Ok(usage_response)
+    fn test_client(server: &MockServer) -> AnthropicClient {
+        AnthropicClient::new("sk-ant-admin-test".to_string())
+            .unwrap()
+            .with_cache_ttl(None)
+            .with_base_url(Some(&server.uri()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetch_usage_sends_auth_headers_and_query() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/organizations/usage_report/claude_code"))
+            .and(header("x-api-key", "sk-ant-admin-test"))
+            .and(header("anthropic-version", "2023-06-01"))
+            .and(header("content-type", "application/json"))
+            .and(query_param("starting_at", "2025-01-06"))
+            .and(query_param("ending_at", "2025-01-13"))
+            .and(query_param("limit", "250"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(USAGE_BODY))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = test_client(&server).with_page_limit(250).unwrap();
+        let from = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 1, 13).unwrap();
+        let response = client.fetch_usage_range(from, to).await.unwrap();
+
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].input_tokens(), 100);
+        assert_eq!(response.data[0].cache_read_tokens(), 40);
+    }
+
+    #[tokio::test]
+    async fn fetch_usage_defaults_to_the_largest_page() {
+        let server = MockServer::start().await;
+        let starting_at = (chrono::Utc::now() - chrono::Duration::days(7)).format("%Y-%m-%d").to_string();
+        Mock::given(method("GET"))
+            .and(query_param("starting_at", starting_at.as_str()))
+            .and(query_param("limit", MAX_PAGE_LIMIT.to_string().as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_string(USAGE_BODY))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        test_client(&server).fetch_usage(7).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn workspace_id_scopes_the_query() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("workspace_ids[]", "wrkspc_01abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(USAGE_BODY))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = test_client(&server).with_workspace_id(Some(" wrkspc_01abc ")).unwrap();
+        client.fetch_usage(7).await.unwrap();
+
+        assert!(test_client(&server).with_workspace_id(Some("  ")).is_err());
+        assert!(test_client(&server).with_workspace_id(Some("../etc")).is_err());
+    }
+
+    /// A first page that points at a second page, `p2`
+    async fn mount_first_page(server: &MockServer) {
+        let body = USAGE_BODY.replace(r#""has_more": false"#, r#""has_more": true, "next_page": "p2""#);
+        Mock::given(method("GET"))
+            .and(query_param_is_missing("page"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn pagination_retries_a_flaky_page() {
+        let server = MockServer::start().await;
+        mount_first_page(&server).await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "p2"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "p2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(USAGE_BODY))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = AnthropicClient { retry_backoff: Duration::ZERO, ..test_client(&server) };
+        let response = client.fetch_usage(7).await.unwrap();
+
+        assert_eq!(response.data.len(), 2);
+        assert!(!response.partial);
+    }
+
+    #[tokio::test]
+    async fn pagination_keeps_earlier_pages_when_a_later_one_fails() {
+        let server = MockServer::start().await;
+        mount_first_page(&server).await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "p2"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad page"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = test_client(&server).fetch_usage(7).await.unwrap();
+
+        assert_eq!(response.data.len(), 1);
+        assert!(response.partial);
+        assert!(!response.has_more);
+    }
+
+    #[tokio::test]
+    async fn raw_fetch_returns_bodies_we_cannot_parse() {
+        let server = MockServer::start().await;
+        let first = r#"{"data": {"new": "shape"}, "has_more": true, "next_page": "p2"}"#;
+        Mock::given(method("GET"))
+            .and(query_param_is_missing("page"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(first))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "p2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(USAGE_BODY))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = test_client(&server);
+        assert!(matches!(client.fetch_usage(7).await, Err(ApiError::Parse(_))));
+
+        let pages = client.fetch_usage_raw(7).await.unwrap();
+        assert_eq!(pages, [first, USAGE_BODY]);
+    }
+
+    #[tokio::test]
+    async fn fetch_cost_follows_pages_and_keeps_the_workspace() {
+        let server = MockServer::start().await;
+        let page = |amounts: &str, more: &str| format!(
+            r#"{{"data": [{{"starting_at": "2025-01-06T00:00:00Z", "ending_at": "2025-01-07T00:00:00Z", "results": [{}]}}], {}}}"#,
+            amounts, more
+        );
+        Mock::given(method("GET"))
+            .and(path("/organizations/cost_report"))
+            .and(query_param("group_by[]", "workspace_id"))
+            .and(query_param_is_missing("page"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page(
+                r#"{"amount": "250", "workspace_id": "wrkspc_a"}, {"amount": "9999", "workspace_id": "wrkspc_b"}"#,
+                r#""has_more": true, "next_page": "p2""#,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/organizations/cost_report"))
+            .and(query_param("page", "p2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page(
+                r#"{"amount": "50.5", "workspace_id": "wrkspc_a"}"#,
+                r#""has_more": false"#,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = test_client(&server).with_workspace_id(Some("wrkspc_a")).unwrap();
+        let response = client.fetch_cost(7).await.unwrap();
+
+        assert_eq!(response.data.len(), 2);
+        assert!((response.total_usd() - 3.005).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cache_keys_differ_by_api_key_without_containing_it() {
+        let client = |key: &str| AnthropicClient::new(key.to_string()).unwrap();
+        let work = client("sk-ant-admin-work").cache_key("2025-01-01", None);
+        let personal = client("sk-ant-admin-personal").cache_key("2025-01-01", None);
+
+        assert_ne!(work, personal);
+        assert_eq!(work, client("sk-ant-admin-work").cache_key("2025-01-01", None));
+        assert!(work.starts_with("2025-01-01_key-"));
+        assert!(!work.contains("sk-ant"));
+    }
+
+    #[test]
+    fn cache_keys_differ_by_base_url_only_when_overridden() {
+        let client = AnthropicClient::new("sk-ant-admin-work".to_string()).unwrap();
+        let default = client.cache_key("2025-01-01", None);
+        assert!(!default.contains("_url-"));
+
+        let gateway = client.with_base_url(Some("https://gateway.example.com/v1")).unwrap();
+        let proxied = gateway.cache_key("2025-01-01", None);
+        assert_ne!(proxied, default);
+        assert!(proxied.starts_with(&default));
+        assert!(!proxied.contains("gateway"));
+    }
+
+    #[test]
+    fn base_url_must_be_http() {
+        let client = || AnthropicClient::new("sk-ant-admin-test".to_string()).unwrap();
+
+        assert_eq!(client().with_base_url(None).unwrap().base_url, API_BASE_URL);
+        assert_eq!(
+            client().with_base_url(Some("https://gateway.example.com/anthropic/v1/")).unwrap().base_url,
+            "https://gateway.example.com/anthropic/v1"
+        );
+        assert!(client().with_base_url(Some("gateway.example.com")).is_err());
+        assert!(client().with_base_url(Some("ftp://gateway.example.com")).is_err());
+    }
+
+    #[tokio::test]
+    async fn rate_limit_carries_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "30"))
+            .mount(&server)
+            .await;
+
+        let err = test_client(&server).fetch_usage(1).await.unwrap_err();
+        assert!(matches!(err, ApiError::RateLimited { retry_after: Some(30) }));
+    }
+
+    #[test]
+    fn auth_failures_are_classified_separately() {
+        let classify = |code| ApiError::from_status(StatusCode::from_u16(code).unwrap(), None, "raw body".to_string());
+
+        assert!(matches!(classify(401), ApiError::Unauthorized));
+        assert!(matches!(classify(403), ApiError::Forbidden));
+        assert!(matches!(classify(404), ApiError::NotFound(_)));
+        assert!(classify(401).to_string().contains("config --api-key"));
+        assert!(!classify(403).to_string().contains("raw body"));
     }
 }
@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::models::{Grouping, UsageRecord, UsageResponse, INVALID_DATE};
+use crate::pricing::ModelPricing;
+
+/// Default time a cached usage response stays fresh
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How long to wait for another run to release the stats lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A lock file older than this was left behind by a run that crashed
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// ~/.cache/claude-token-counter
+fn cache_dir() -> Result<PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("Could not find cache directory")?
+        .join("claude-token-counter"))
+}
+
+/// Short hex digest of `value` for keeping cache files apart without writing `value`
+/// itself (e.g. an API key) into a file name; not for security or long-term storage
+pub fn short_hash(value: &str) -> String {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("{:012x}", hasher.finish() & 0xffff_ffff_ffff)
+}
+
+/// A usage response snapshot stored on disk with the time it was fetched
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    pub fetched_at: DateTime<Utc>,
+    pub response: UsageResponse,
+}
+
+impl UsageSnapshot {
+    /// Get the path to the snapshot for a query key (the query date or date range)
+    /// Returns: ~/.cache/claude-token-counter/usage-<key>.json
+    fn cache_path(date: &str) -> Result<PathBuf> {
+        Ok(cache_dir()?.join(format!("usage-{}.json", date)))
+    }
+
+    /// Load the snapshot for a query date if it exists and is younger than `ttl`
+    pub fn load_fresh(date: &str, ttl: Duration) -> Option<Self> {
+        let path = Self::cache_path(date).ok()?;
+        let contents = fs::read_to_string(path).ok()?;
+        let snapshot: UsageSnapshot = serde_json::from_str(&contents).ok()?;
+
+        let age = Utc::now().signed_duration_since(snapshot.fetched_at).to_std().ok()?;
+        if age <= ttl {
+            Some(snapshot)
+        } else {
+            None
+        }
+    }
+
+    /// Wrap a freshly fetched response, stamped with the current time
+    pub fn new(response: UsageResponse) -> Self {
+        Self { fetched_at: Utc::now(), response }
+    }
+
+    /// Save this snapshot for a query date
+    /// Creates the directory if it doesn't exist
+    pub fn save(&self, date: &str) -> Result<()> {
+        let path = Self::cache_path(date)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .context("Could not create cache directory")?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)
+            .context("Could not serialize usage snapshot")?;
+
+        fs::write(&path, contents)
+            .with_context(|| format!("Could not write usage snapshot to {:?}", path))?;
+
+        Ok(())
+    }
+}
+
+/// The highest value of some measure seen so far, and when it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Peak<T> {
+    pub value: T,
+    /// Day (YYYY-MM-DD) or month (YYYY-MM) the peak fell in
+    pub period: String,
+}
+
+/// All-time high-water marks, kept across runs in ~/.cache/claude-token-counter/stats_{scope}.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersonalBests {
+    /// Most tokens (of every kind) used in a single day
+    #[serde(default)]
+    pub peak_day_tokens: Option<Peak<u64>>,
+    /// Highest estimated cost of a calendar month
+    #[serde(default)]
+    pub peak_month_cost: Option<Peak<f64>>,
+}
+
+impl PersonalBests {
+    /// The peaks within `records` alone
+    pub fn from_records(records: &[UsageRecord], pricing: &ModelPricing) -> Self {
+        let mut days: BTreeMap<String, u64> = BTreeMap::new();
+        let mut months: BTreeMap<String, f64> = BTreeMap::new();
+        for record in records {
+            let date = record.date();
+            if date == INVALID_DATE {
+                continue;
+            }
+            *months.entry(Grouping::Month.bucket_key(&date)).or_default() += record.estimated_cost(pricing);
+            *days.entry(date).or_default() += record.total();
+        }
+
+        // Days and months with no usage aren't worth remembering
+        let peak_day_tokens = days.into_iter()
+            .filter(|&(_, tokens)| tokens > 0)
+            .max_by_key(|&(_, tokens)| tokens)
+            .map(|(period, value)| Peak { value, period });
+        let peak_month_cost = months.into_iter()
+            .filter(|&(_, cost)| cost > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(period, value)| Peak { value, period });
+
+        Self { peak_day_tokens, peak_month_cost }
+    }
+
+    /// Keep the higher of each peak; ties keep the earlier record
+    pub fn merge(&mut self, other: PersonalBests) {
+        if let Some(day) = other.peak_day_tokens {
+            if self.peak_day_tokens.as_ref().is_none_or(|best| day.value > best.value) {
+                self.peak_day_tokens = Some(day);
+            }
+        }
+        if let Some(month) = other.peak_month_cost {
+            if self.peak_month_cost.as_ref().is_none_or(|best| month.value > best.value) {
+                self.peak_month_cost = Some(month);
+            }
+        }
+    }
+
+    /// Fold the peaks in `records` into the stats saved for `scope` and return the result
+    /// `scope` tells profiles and workspaces apart (see `AnthropicClient::scope`)
+    ///
+    /// Runs at the same time take turns through a lock file, and the new stats
+    /// replace the old with a rename, so a reader never sees a half-written file
+    pub fn record(records: &[UsageRecord], pricing: &ModelPricing, scope: &str) -> Result<Self> {
+        let dir = cache_dir()?;
+        fs::create_dir_all(&dir).context("Could not create cache directory")?;
+        Self::record_in(&dir, scope, Self::from_records(records, pricing))
+    }
+
+    fn record_in(dir: &Path, scope: &str, observed: PersonalBests) -> Result<Self> {
+        let name = format!("stats_{}.json", scope);
+        let path = dir.join(&name);
+        let _lock = LockFile::acquire(&dir.join(format!("{}.lock", name)))?;
+
+        // A missing or unreadable file just starts the records over
+        let mut bests: PersonalBests = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let before = bests.clone();
+        bests.merge(observed);
+        if bests == before {
+            return Ok(bests);
+        }
+
+        let temp = dir.join(format!("{}.{}.tmp", name, std::process::id()));
+        let contents = serde_json::to_string_pretty(&bests).context("Could not serialize stats")?;
+        fs::write(&temp, contents).with_context(|| format!("Could not write stats to {:?}", temp))?;
+        fs::rename(&temp, &path).with_context(|| format!("Could not replace stats at {:?}", path))?;
+
+        Ok(bests)
+    }
+}
+
+/// Exclusive lock held by creating a file, removed again on drop
+struct LockFile(PathBuf);
+
+impl LockFile {
+    /// Wait up to `LOCK_TIMEOUT` for `path` to be free, taking over locks left by crashed runs
+    fn acquire(path: &Path) -> Result<Self> {
+        let started = SystemTime::now();
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(Self(path.to_path_buf())),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(path)
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK_AGE);
+                    if stale {
+                        let _ = fs::remove_file(path);
+                        continue;
+                    }
+                    if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        anyhow::bail!("Timed out waiting for another run to release {:?}", path);
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(e).with_context(|| format!("Could not create lock file {:?}", path)),
+            }
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UsageDetail;
+
+    fn record(date: &str, input: u64) -> UsageRecord {
+        UsageRecord {
+            starting_at: format!("{}T00:00:00Z", date),
+            ending_at: format!("{}T23:59:59Z", date),
+            results: vec![UsageDetail {
+                input_tokens: input,
+                output_tokens: 0,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                reasoning_tokens: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn personal_bests_only_ever_go_up() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+
+        let first = [record("2025-01-06", 1_000_000), record("2025-01-07", 3_000_000), record("2025-02-01", 500_000)];
+        let bests = PersonalBests::record_in(&dir, "key-work", PersonalBests::from_records(&first, &pricing)).unwrap();
+        assert_eq!(bests.peak_day_tokens, Some(Peak { value: 3_000_000, period: "2025-01-07".to_string() }));
+        assert_eq!(bests.peak_month_cost, Some(Peak { value: 12.0, period: "2025-01".to_string() }));
+
+        // A quieter run leaves the saved peaks alone; a busier day replaces just that one
+        let second = [record("2025-03-02", 4_000_000)];
+        let bests = PersonalBests::record_in(&dir, "key-work", PersonalBests::from_records(&second, &pricing)).unwrap();
+        assert_eq!(bests.peak_day_tokens, Some(Peak { value: 4_000_000, period: "2025-03-02".to_string() }));
+        assert_eq!(bests.peak_month_cost.map(|peak| peak.period), Some("2025-01".to_string()));
+        assert!(!dir.join("stats_key-work.json.lock").exists());
+
+        // Another profile or workspace keeps peaks of its own
+        let other = [record("2025-03-03", 2_000_000)];
+        let bests = PersonalBests::record_in(&dir, "ws-wrkspc_01_key-personal", PersonalBests::from_records(&other, &pricing)).unwrap();
+        assert_eq!(bests.peak_day_tokens, Some(Peak { value: 2_000_000, period: "2025-03-03".to_string() }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
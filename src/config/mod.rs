@@ -1,72 +1,841 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-/// Configuration structure that holds the API key
+use crate::pricing::{self, ModelPricing};
+use crate::display::ColorThresholds;
+use crate::local;
+use crate::util::{Currency, NumberFormat};
+
+/// Environment variable that overrides the config file location
+pub const CONFIG_PATH_ENV: &str = "CLAUDE_TOKEN_COUNTER_CONFIG";
+
+/// Config file in the current directory, used when no config directory can be found
+pub const LOCAL_CONFIG_FILE: &str = ".claude-token-counter.json";
+
+/// On-disk serialization format, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// `.toml` files are TOML; everything else is treated as JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+/// Default minimum live refresh interval in seconds
+pub const DEFAULT_REFRESH_FLOOR: f64 = 1.0;
+
+/// Lowest `refresh_floor` the config may set, in seconds
+pub const MIN_REFRESH_FLOOR: f64 = 0.1;
+
+/// Config schema version written by this build
+/// Version 0 is any file from before versioning; 1 introduced named profiles
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Environment variable holding an API key, used instead of the config file's key
+pub const API_KEY_ENV: &str = "CLAUDE_TOKEN_COUNTER_API_KEY";
+
+/// Name of the profile used when none is selected (and for migrated configs)
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Credentials and limits for a single Anthropic organization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Stored API key (empty when `api_key_file` is used instead)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_key: String,
+
+    /// File whose first line is the API key, read at runtime (e.g. a mounted secret)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_file: Option<PathBuf>,
+
+    /// Monthly token limit for this profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<u64>,
+
+    /// Daily token limit for this profile, tracked alongside the monthly one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<u64>,
+}
+
+impl Profile {
+    /// Create a new Profile with the given API key and no limit
+    pub fn new(api_key: String) -> Self {
+        Self { api_key, api_key_file: None, monthly_limit: None, daily_limit: None }
+    }
+
+    /// A copy of this profile with `api_key` read from `api_key_file` when one is set
+    pub fn resolved(&self) -> Result<Profile> {
+        let api_key = match &self.api_key_file {
+            Some(path) => read_api_key_file(path)?,
+            None if self.api_key.is_empty() => {
+                anyhow::bail!("Profile has neither `api_key` nor `api_key_file`. Run 'config --api-key YOUR_KEY'")
+            }
+            None => self.api_key.clone(),
+        };
+        Ok(Profile { api_key, api_key_file: None, ..self.clone() })
+    }
+
+    /// API key with everything but the first 8 and last 4 characters hidden
+    /// Profiles that read their key from a file show the path instead
+    pub fn masked_api_key(&self) -> String {
+        if let Some(path) = &self.api_key_file {
+            return format!("(read from {})", path.display());
+        }
+
+        let chars: Vec<char> = self.api_key.chars().collect();
+        if chars.len() <= 12 {
+            return "*".repeat(chars.len());
+        }
+
+        let head: String = chars[..8].iter().collect();
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("{}...{}", head, tail)
+    }
+}
+
+/// Read an API key from the first line of `path`, trimming whitespace
+pub fn read_api_key_file(path: &Path) -> Result<String> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read API key file {:?}", path))?;
+    let key = contents.lines().next().unwrap_or_default().trim();
+    if key.is_empty() {
+        anyhow::bail!("API key file {:?} is empty", path);
+    }
+    Ok(key.to_string())
+}
+
+/// API key given outside the config file: `key_file` (from `--api-key-file`) first,
+/// then the `CLAUDE_TOKEN_COUNTER_API_KEY` environment variable
+pub fn api_key_override(key_file: Option<&Path>) -> Result<Option<String>> {
+    if let Some(path) = key_file {
+        return read_api_key_file(path).map(Some);
+    }
+    Ok(std::env::var(API_KEY_ENV).ok().map(|key| key.trim().to_string()).filter(|key| !key.is_empty()))
+}
+
+/// Settings for one Claude Code project, from a `[projects."name"]` table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    /// Monthly token limit for this project, shown by `top-projects`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<u64>,
+}
+
+/// Configuration structure that holds named API key profiles
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
-    pub api_key: String,
+    /// Schema version the file was written with (missing in pre-versioning files)
+    #[serde(default)]
+    pub version: u32,
+
+    /// Profile used when `--profile` is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
+    /// Named profiles, each with its own API key and limit
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Per-project settings, keyed by directory name, original path, or repo name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, ProjectSettings>,
+
+    /// Per-model pricing overrides (dollars per million tokens)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<HashMap<String, ModelPricing>>,
+
+    /// Minimum live refresh interval in seconds (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_floor: Option<f64>,
+
+    /// Currency costs are shown in, e.g. "EUR" (default: USD)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+
+    /// Units of `currency` per US dollar, supplied by the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fx_rate: Option<f64>,
+
+    /// API root to use instead of https://api.anthropic.com/v1 (e.g. an LLM gateway)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+
+    /// Only report usage from this workspace instead of the whole organization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+
+    /// Percent of the monthly limit at which usage turns yellow (default: 50)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_percent: Option<f64>,
+
+    /// Percent of the monthly limit at which usage turns bright yellow (default: 80)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub critical_percent: Option<f64>,
+
+    /// History rows above this many tokens turn yellow (default: 50,000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_tokens: Option<u64>,
+
+    /// History rows above this many tokens turn red (default: 100,000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub critical_tokens: Option<u64>,
+
+    /// Count cache tokens in the `status` and `budget` headline total (default: false)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_cache: Option<bool>,
+
+    /// Thousands separator for token counts, e.g. "." or " " (default: ",")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thousands_sep: Option<String>,
+
+    /// Single API key from configs written before profiles existed
+    #[serde(default, rename = "api_key", skip_serializing)]
+    legacy_api_key: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            default_profile: None,
+            profiles: BTreeMap::new(),
+            projects: BTreeMap::new(),
+            pricing: None,
+            refresh_floor: None,
+            currency: None,
+            fx_rate: None,
+            api_base_url: None,
+            workspace_id: None,
+            warn_percent: None,
+            critical_percent: None,
+            warn_tokens: None,
+            critical_tokens: None,
+            include_cache: None,
+            thousands_sep: None,
+            legacy_api_key: None,
+        }
+    }
+}
+
+/// Just enough of a config file to check its version before parsing the rest
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Attempts at writing the config before giving up
+const SAVE_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry of a failed write, doubled after each one
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Run `op` until it succeeds, backing off between up to `SAVE_ATTEMPTS` tries
+/// A missing directory won't appear by waiting, so `NotFound` fails straight away
+fn with_retries(mut op: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    let mut delay = SAVE_RETRY_DELAY;
+    for attempt in 1.. {
+        match op() {
+            Err(e) if attempt < SAVE_ATTEMPTS && e.kind() != io::ErrorKind::NotFound => {
+                tracing::debug!(attempt, error = %e, "config write failed, retrying");
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// Replace `path` with `contents` by writing a temp file beside it and renaming it over
+/// `path`, so a failed or interrupted save never leaves a truncated config behind
+/// On Unix the temp file is created owner-only (0600), so the keys are never readable by others
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp)?;
+
+        // A temp file left over from a crash keeps its old mode, so set it again
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Directory holding per-user config: the platform's own, else `$XDG_CONFIG_HOME`,
+/// else `$HOME/.config`, for minimal containers where the platform lookup fails
+fn config_base_dir(platform: Option<PathBuf>, xdg_config_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    // Empty or relative values are ignored, as the XDG spec asks
+    let absolute = |value: Option<OsString>| value.map(PathBuf::from).filter(|path| path.is_absolute());
+
+    platform
+        .or_else(|| absolute(xdg_config_home))
+        .or_else(|| absolute(home).map(|home| home.join(".config")))
 }
 
 impl Config {
     /// Get the path to the config file
-    /// Returns: ~/.config/claude-token-counter/config.json
-    fn config_path() -> Result<PathBuf> {
-        let config_dir = dirs::config_dir()
-            .context("Could not find config directory")?
-            .join("claude-token-counter");
+    /// Resolution order: explicit `path_override` (e.g. `--config`), then the
+    /// `CLAUDE_TOKEN_COUNTER_CONFIG` environment variable, then
+    /// ~/.config/claude-token-counter/config.toml (or an existing config.json
+    /// from older versions), and finally `./.claude-token-counter.json` when
+    /// no config directory can be found (see `config_base_dir`)
+    pub fn config_path(path_override: Option<&Path>) -> Result<PathBuf> {
+        if let Some(path) = path_override {
+            return Ok(path.to_path_buf());
+        }
+
+        if let Some(path) = std::env::var_os(CONFIG_PATH_ENV).filter(|v| !v.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
 
-        Ok(config_dir.join("config.json"))
+        let config_dir = config_base_dir(
+            dirs::config_dir(),
+            std::env::var_os("XDG_CONFIG_HOME"),
+            std::env::var_os("HOME"),
+        );
+        match config_dir {
+            Some(config_dir) => {
+                let dir = config_dir.join("claude-token-counter");
+                let legacy_json = dir.join("config.json");
+                let toml = dir.join("config.toml");
+
+                // Keep using a JSON config written by an older version until it's removed
+                if !toml.exists() && legacy_json.exists() {
+                    Ok(legacy_json)
+                } else {
+                    Ok(toml)
+                }
+            }
+            None => {
+                tracing::warn!(
+                    "Could not find a config directory (no XDG_CONFIG_HOME or HOME); using {} in the current directory",
+                    LOCAL_CONFIG_FILE
+                );
+                Ok(PathBuf::from(LOCAL_CONFIG_FILE))
+            }
+        }
     }
 
     /// Load configuration from disk
     /// Returns the Config if it exists, or an error if not found
-    pub fn load() -> Result<Self> {
-        let path = Self::config_path()?;
+    pub fn load(path_override: Option<&Path>) -> Result<Self> {
+        let path = Self::config_path(path_override)?;
 
         let contents = fs::read_to_string(&path)
             .with_context(|| format!("Could not read config file at {:?}", path))?;
 
-        let config: Config = serde_json::from_str(&contents)
-            .context("Could not parse config file")?;
+        // Refuse newer files up front: their fields may not parse, and saving
+        // them back would silently drop whatever this build doesn't know about
+        let format = ConfigFormat::from_path(&path);
+        let probe: VersionProbe = match format {
+            ConfigFormat::Json => serde_json::from_str(&contents)
+                .with_context(|| format!("Could not parse config file at {:?}", path))?,
+            ConfigFormat::Toml => toml::from_str(&contents)
+                .with_context(|| format!("Could not parse config file at {:?}", path))?,
+        };
+        if probe.version > CONFIG_VERSION {
+            anyhow::bail!(
+                "Config file at {:?} was written by a newer version of claude-token-counter \
+                 (config version {}, this build supports up to {}). Upgrade to use it.",
+                path,
+                probe.version,
+                CONFIG_VERSION
+            );
+        }
+
+        let mut config: Config = match format {
+            ConfigFormat::Json => serde_json::from_str(&contents)
+                .with_context(|| format!("Could not parse config file at {:?}", path))?,
+            ConfigFormat::Toml => toml::from_str(&contents)
+                .with_context(|| format!("Could not parse config file at {:?}", path))?,
+        };
+
+        config.migrate();
 
         Ok(config)
     }
 
+    /// Load configuration, or defaults when no config file exists yet
+    /// An unreadable, malformed, or newer-version file is still an error, so it's never
+    /// quietly replaced by defaults (and then overwritten by a save)
+    pub fn load_or_default(path_override: Option<&Path>) -> Result<Self> {
+        let path = Self::config_path(path_override)?;
+        match fs::metadata(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            _ => Self::load(Some(&path)),
+        }
+    }
+
     /// Save configuration to disk
     /// Creates the directory if it doesn't exist
-    pub fn save(&self) -> Result<()> {
-        let path = Self::config_path()?;
+    pub fn save(&self, path_override: Option<&Path>) -> Result<()> {
+        let path = Self::config_path(path_override)?;
 
         // Create the directory if it doesn't exist
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
-                .context("Could not create config directory")?;
+                .with_context(|| format!("Could not create config directory {:?} (pass --config to save elsewhere)", parent))?;
         }
 
-        // Serialize and write the config
-        let contents = serde_json::to_string_pretty(self)
-            .context("Could not serialize config")?;
+        // Serialize in the format matching the file extension and write the config
+        let contents = match ConfigFormat::from_path(&path) {
+            ConfigFormat::Json => serde_json::to_string_pretty(self)
+                .context("Could not serialize config")?,
+            ConfigFormat::Toml => toml::to_string_pretty(self)
+                .context("Could not serialize config")?,
+        };
 
-        fs::write(&path, contents)
+        // Another process (a sync client, an editor, antivirus) may briefly hold the file
+        with_retries(|| write_atomically(&path, &contents))
             .with_context(|| format!("Could not write config file to {:?}", path))?;
 
-        // Set restrictive permissions on Unix-like systems (macOS, Linux)
+        crate::display::info(format!("Configuration saved to: {:?}", path));
+        Ok(())
+    }
+
+    /// Delete the config file from disk
+    /// Returns the removed path, or None if no config was present
+    pub fn clear(path_override: Option<&Path>) -> Result<Option<PathBuf>> {
+        let path = Self::config_path(path_override)?;
+
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(Some(path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Could not remove config file at {:?}", path)),
+        }
+    }
+
+    /// Upgrade an older config in memory to `CONFIG_VERSION`
+    /// Saving afterwards writes the upgraded form
+    fn migrate(&mut self) {
+        if self.version < 1 {
+            self.migrate_legacy_key();
+        }
+        self.version = CONFIG_VERSION;
+    }
+
+    /// Move a pre-profiles single `api_key` into the `default` profile
+    fn migrate_legacy_key(&mut self) {
+        if let Some(api_key) = self.legacy_api_key.take() {
+            self.profiles
+                .entry(DEFAULT_PROFILE.to_string())
+                .or_insert_with(|| Profile::new(api_key));
+            self.default_profile.get_or_insert_with(|| DEFAULT_PROFILE.to_string());
+        }
+    }
+
+    /// Name of the profile to use: the explicit selection, else the configured default
+    pub fn profile_name<'a>(&'a self, selected: Option<&'a str>) -> &'a str {
+        selected
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// Look up the selected (or default) profile
+    pub fn profile(&self, selected: Option<&str>) -> Result<&Profile> {
+        let name = self.profile_name(selected);
+        self.profiles.get(name).with_context(|| {
+            format!(
+                "No profile named '{}' configured. Run 'config --api-key YOUR_KEY --profile {}' first",
+                name, name
+            )
+        })
+    }
+
+    /// Get a mutable profile, creating it with the given API key if missing
+    /// The first profile created becomes the default
+    pub fn upsert_profile(&mut self, name: &str, api_key: String) -> &mut Profile {
+        self.default_profile.get_or_insert_with(|| name.to_string());
+
+        let profile = self
+            .profiles
+            .entry(name.to_string())
+            .or_insert_with(|| Profile::new(api_key.clone()));
+        profile.api_key = api_key;
+        profile.api_key_file = None;
+        profile
+    }
+
+    /// Minimum live refresh interval, never lower than `MIN_REFRESH_FLOOR`
+    pub fn refresh_floor(&self) -> f64 {
+        self.refresh_floor
+            .filter(|floor| floor.is_finite())
+            .unwrap_or(DEFAULT_REFRESH_FLOOR)
+            .max(MIN_REFRESH_FLOOR)
+    }
+
+    /// Resolve the display currency, with `--currency` / `--fx-rate` taking precedence
+    /// The configured `fx_rate` only applies to the configured `currency`
+    pub fn currency(&self, code: Option<&str>, rate: Option<f64>) -> Result<Currency> {
+        let configured = self.currency.as_deref().map(str::to_uppercase);
+        let code = code.map(str::to_uppercase).or(configured.clone()).unwrap_or_else(|| "USD".to_string());
+
+        let rate = match rate {
+            Some(rate) => rate,
+            None if code == "USD" => 1.0,
+            None if configured.as_deref() == Some(code.as_str()) => self.fx_rate.with_context(|| {
+                format!("No exchange rate for {}. Set `fx_rate` in the config file or pass --fx-rate", code)
+            })?,
+            None => anyhow::bail!("No exchange rate for {}. Pass --fx-rate (units of {} per US dollar)", code, code),
+        };
+        if !rate.is_finite() || rate <= 0.0 {
+            anyhow::bail!("Exchange rate must be a positive number, got {}", rate);
+        }
+
+        Ok(Currency { code, rate })
+    }
+
+    /// Color thresholds with any configured overrides applied
+    /// A critical threshold below its warning threshold is raised to match it
+    pub fn color_thresholds(&self) -> ColorThresholds {
+        let defaults = ColorThresholds::default();
+        let warn_percent = self.warn_percent.filter(|p| p.is_finite()).unwrap_or(defaults.warn_percent);
+        let critical_percent = self.critical_percent.filter(|p| p.is_finite()).unwrap_or(defaults.critical_percent);
+        let warn_tokens = self.warn_tokens.unwrap_or(defaults.warn_tokens);
+        let critical_tokens = self.critical_tokens.unwrap_or(defaults.critical_tokens);
+
+        ColorThresholds {
+            warn_percent,
+            critical_percent: critical_percent.max(warn_percent),
+            warn_tokens,
+            critical_tokens: critical_tokens.max(warn_tokens),
+        }
+    }
+
+    /// Number format for token counts, abbreviated when `abbreviate` (from `--abbrev`) is set
+    /// `plain` (from `--plain-numbers`) wins over both, printing raw digits
+    pub fn number_format(&self, abbreviate: bool, plain: bool) -> Result<NumberFormat> {
+        let mut format = NumberFormat { abbreviate, ..NumberFormat::default() };
+        if let Some(sep) = &self.thousands_sep {
+            if sep.chars().any(|c| c.is_ascii_digit()) {
+                anyhow::bail!("`thousands_sep` must not contain digits, got {:?}", sep);
+            }
+            format.thousands_sep = sep.clone();
+        }
+        if plain {
+            format = NumberFormat::plain();
+        }
+        Ok(format)
+    }
+
+    /// Get pricing for a model, consulting configured overrides first
+    pub fn pricing_for(&self, model: &str) -> ModelPricing {
+        pricing::resolve_pricing(model, self.pricing.as_ref())
+    }
+
+    /// Monthly limit configured for the project directory `dir_name`
+    /// When several `[projects]` keys match, the longest (most specific) one wins
+    pub fn project_limit(&self, dir_name: &str) -> Option<u64> {
+        self.projects
+            .iter()
+            .filter(|(project, settings)| settings.monthly_limit.is_some() && local::project_matches(dir_name, project))
+            .max_by_key(|(project, _)| project.len())
+            .and_then(|(_, settings)| settings.monthly_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config path inside a fresh per-test temp directory
+    fn temp_config_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("claude-token-counter-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("nested").join("config.json")
+    }
+
+    /// Save a config with a profile and pricing override to `path` and load it back
+    fn assert_round_trip(path: &Path) {
+        let mut config = Config::default();
+        config.upsert_profile("work", "sk-ant-admin-test-key-0001".to_string()).monthly_limit = Some(42);
+        config.upsert_profile("personal", "sk-ant-admin-test-key-0002".to_string());
+        config.pricing = Some(HashMap::from([(
+            "claude-sonnet-4-5".to_string(),
+            ModelPricing { input: 1.0, output: 2.0, cache_write: 3.0, cache_read: 4.0, reasoning: None, cache_write_1h: None },
+        )]));
+        config.save(Some(path)).unwrap();
+
+        let loaded = Config::load(Some(path)).unwrap();
+        assert_eq!(loaded.profile_name(None), "work");
+        assert_eq!(loaded.profiles.len(), 2);
+        assert_eq!(loaded.profile(None).unwrap().monthly_limit, Some(42));
+        assert_eq!(loaded.profile(Some("personal")).unwrap().monthly_limit, None);
+        assert_eq!(loaded.pricing_for("claude-sonnet-4-5").cache_read, 4.0);
+    }
+
+    #[test]
+    fn round_trips_through_json_and_toml() {
+        for ext in ["json", "toml"] {
+            let path = temp_config_path(&format!("format-{}", ext)).with_extension(ext);
+            assert_round_trip(&path);
+
+            let contents = fs::read_to_string(&path).unwrap();
+            assert_eq!(contents.trim_start().starts_with('{'), ext == "json");
+
+            fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn config_dir_falls_back_to_xdg_then_home() {
+        let platform = Some(PathBuf::from("/platform"));
+        let xdg = || Some(OsString::from("/xdg"));
+        let home = || Some(OsString::from("/home/me"));
+
+        assert_eq!(config_base_dir(platform, xdg(), home()), Some(PathBuf::from("/platform")));
+        assert_eq!(config_base_dir(None, xdg(), home()), Some(PathBuf::from("/xdg")));
+        assert_eq!(config_base_dir(None, Some(OsString::from("relative")), home()), Some(PathBuf::from("/home/me/.config")));
+        assert_eq!(config_base_dir(None, Some(OsString::new()), Some(OsString::new())), None);
+        assert_eq!(config_base_dir(None, None, None), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_through_override_path() {
+        let path = temp_config_path("round-trip");
+
+        let mut config = Config::default();
+        config.upsert_profile("work", "sk-ant-admin-test-key-0001".to_string()).monthly_limit = Some(42);
+        config.save(Some(&path)).unwrap();
+
+        let loaded = Config::load(Some(&path)).unwrap();
+        let profile = loaded.profile(None).unwrap();
+        assert_eq!(loaded.profile_name(None), "work");
+        assert_eq!(profile.api_key, "sk-ant-admin-test-key-0001");
+        assert_eq!(profile.monthly_limit, Some(42));
+
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&path)?.permissions();
-            perms.set_mode(0o600); // Read/write for owner only
-            fs::set_permissions(&path, perms)?;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
         }
 
-        println!("Configuration saved to: {:?}", path);
-        Ok(())
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
     }
 
-    /// Create a new Config with the given API key
-    pub fn new(api_key: String) -> Self {
-        Self { api_key }
+    #[test]
+    fn atomic_write_replaces_the_file_through_a_rename() {
+        let path = temp_config_path("atomic");
+        let dir = path.parent().unwrap();
+        fs::create_dir_all(dir).unwrap();
+        fs::write(&path, "old contents that are longer than the new ones").unwrap();
+
+        // Hard links share an inode; a rename swaps in a new one, an in-place write wouldn't
+        let link = dir.join("link-to-old");
+        fs::hard_link(&path, &link).unwrap();
+
+        write_atomically(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&link).unwrap(), "old contents that are longer than the new ones");
+        let names: Vec<_> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names.len(), 2, "temp file left behind: {:?}", names);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn writes_are_retried_unless_the_directory_is_missing() {
+        let mut calls = 0;
+        let result = with_retries(|| {
+            calls += 1;
+            if calls < 3 { Err(io::Error::from(io::ErrorKind::PermissionDenied)) } else { Ok(()) }
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result = with_retries(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn missing_config_error_names_the_path() {
+        let path = temp_config_path("missing");
+
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(err.to_string().starts_with("Could not read config file at"));
+        assert!(err.to_string().contains("config.json"));
+    }
+
+    #[test]
+    fn malformed_config_error_includes_the_parse_failure() {
+        for (ext, contents) in [("json", "{\"profiles\": "), ("toml", "profiles = [")] {
+            let path = temp_config_path(&format!("malformed-{}", ext)).with_extension(ext);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+
+            let err = Config::load(Some(&path)).unwrap_err();
+            assert!(err.to_string().starts_with("Could not parse config file at"));
+            assert!(format!("{:#}", err).len() > err.to_string().len());
+
+            fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn legacy_single_key_migrates_to_default_profile() {
+        let path = temp_config_path("legacy");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, r#"{"api_key":"sk-ant-admin-legacy-0002"}"#).unwrap();
+
+        let loaded = Config::load(Some(&path)).unwrap();
+        assert_eq!(loaded.version, CONFIG_VERSION);
+        assert_eq!(loaded.profile_name(None), DEFAULT_PROFILE);
+        assert_eq!(loaded.profile(None).unwrap().api_key, "sk-ant-admin-legacy-0002");
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn newer_config_version_is_rejected() {
+        let path = temp_config_path("newer");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let newer = CONFIG_VERSION + 1;
+        fs::write(&path, format!(r#"{{"version":{},"profiles":{{"default":{{"keys":[]}}}}}}"#, newer)).unwrap();
+
+        let err = Config::load(Some(&path)).unwrap_err();
+        assert!(err.to_string().contains("newer version"));
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn refresh_floor_defaults_and_clamps() {
+        let mut config = Config::default();
+        assert_eq!(config.refresh_floor(), DEFAULT_REFRESH_FLOOR);
+
+        config.refresh_floor = Some(0.5);
+        assert_eq!(config.refresh_floor(), 0.5);
+
+        config.refresh_floor = Some(0.0);
+        assert_eq!(config.refresh_floor(), MIN_REFRESH_FLOOR);
+    }
+
+    #[test]
+    fn currency_rate_comes_from_flag_or_matching_config() {
+        let config = Config { currency: Some("eur".to_string()), fx_rate: Some(0.9), ..Config::default() };
+
+        assert_eq!(config.currency(None, None).unwrap(), Currency { code: "EUR".to_string(), rate: 0.9 });
+        assert_eq!(config.currency(Some("usd"), None).unwrap(), Currency::default());
+        assert_eq!(config.currency(Some("GBP"), Some(0.8)).unwrap().rate, 0.8);
+        assert!(config.currency(Some("GBP"), None).is_err());
+        assert!(config.currency(None, Some(0.0)).is_err());
+        assert_eq!(Config::default().currency(None, None).unwrap(), Currency::default());
+    }
+
+    #[test]
+    fn api_key_file_is_read_and_trimmed() {
+        let path = temp_config_path("key-file");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let key_file = path.with_file_name("api-key");
+        fs::write(&key_file, "  sk-ant-admin-from-file  \nignored second line\n").unwrap();
+
+        let profile = Profile { api_key_file: Some(key_file.clone()), ..Profile::new("sk-ant-admin-stored".to_string()) };
+        assert_eq!(profile.resolved().unwrap().api_key, "sk-ant-admin-from-file");
+        assert_eq!(api_key_override(Some(&key_file)).unwrap().as_deref(), Some("sk-ant-admin-from-file"));
+
+        fs::write(&key_file, "\n").unwrap();
+        assert!(profile.resolved().is_err());
+        assert!(Profile::new(String::new()).resolved().is_err());
+
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn number_format_uses_configured_separator() {
+        assert_eq!(Config::default().number_format(false, false).unwrap(), NumberFormat::default());
+
+        let config = Config { thousands_sep: Some(" ".to_string()), ..Config::default() };
+        let format = config.number_format(true, false).unwrap();
+        assert_eq!(format.thousands_sep, " ");
+        assert!(format.abbreviate);
+
+        // --plain-numbers beats both the separator and --abbrev
+        assert_eq!(config.number_format(true, true).unwrap(), NumberFormat::plain());
+
+        let config = Config { thousands_sep: Some("0".to_string()), ..Config::default() };
+        assert!(config.number_format(false, false).is_err());
+    }
+
+    #[test]
+    fn color_thresholds_default_and_keep_critical_above_warn() {
+        assert_eq!(Config::default().color_thresholds(), ColorThresholds::default());
+
+        let config = Config { warn_percent: Some(90.0), critical_tokens: Some(10), ..Config::default() };
+        let thresholds = config.color_thresholds();
+        assert_eq!(thresholds.warn_percent, 90.0);
+        assert_eq!(thresholds.critical_percent, 90.0);
+        assert_eq!(thresholds.critical_tokens, thresholds.warn_tokens);
+    }
+
+    #[test]
+    fn project_limits_match_like_project_names() {
+        let config: Config = toml::from_str(
+            r#"
+            [projects."my-repo"]
+            monthly_limit = 1000
+
+            [projects."/home/me/work/my-repo"]
+            monthly_limit = 2000
+
+            [projects."-home-me-scratch"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.project_limit("-home-me-oss-my-repo"), Some(1000));
+        assert_eq!(config.project_limit("-home-me-work-my-repo"), Some(2000));
+        assert_eq!(config.project_limit("-home-me-scratch"), None);
+        assert_eq!(config.project_limit("-home-me-other"), None);
     }
 }
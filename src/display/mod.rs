@@ -1,61 +1,436 @@
-use crate::models::{UsageSummary, UsageRecord};
+use crate::cache::PersonalBests;
+use crate::doctor::{Check, CheckStatus, Info};
+use crate::models::{self, DailyCost, Grouping, MonthForecast, ProjectTotal, RankBy, ReportPeriod, SortKey, SortOrder, SourceTotals, UsageSummary, UsageRecord};
+use crate::pricing::ModelPricing;
+use crate::util::{self, format_number, format_ratio, format_signed_number, Currency, NumberFormat};
 use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::future::Future;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Hint shown when the API query returned no records at all
+const NO_RECORDS_HINT: &str = "No usage records returned for this period — check your admin key and date range.";
+
+/// Set by `--quiet` to suppress informational output
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable quiet mode for the rest of the process
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether informational output is suppressed
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Set by `--effective-tokens` to add an input-equivalent prompt total
+static EFFECTIVE_TOKENS: AtomicBool = AtomicBool::new(false);
+
+/// Show effective input tokens alongside token totals for the rest of the process
+pub fn set_effective_tokens(show: bool) {
+    EFFECTIVE_TOKENS.store(show, Ordering::Relaxed);
+}
+
+/// Whether effective input tokens are shown
+pub fn shows_effective_tokens() -> bool {
+    EFFECTIVE_TOKENS.load(Ordering::Relaxed)
+}
+
+/// Label row for `ModelPricing::effective_input_tokens`, with the cache-read discount it applied
+pub fn format_effective_tokens(pricing: &ModelPricing, input: u64, cache_creation: u64, cache_read: u64) -> String {
+    let effective = pricing.effective_input_tokens(input, cache_creation, cache_read);
+    let note = if pricing.input > 0.0 {
+        format!("(cache reads at {:.0}% of input)", pricing.cache_read / pricing.input * 100.0)
+    } else {
+        "(cache reads in full)".to_string()
+    };
+    format!("{} {}", format_number(effective.round() as u64).bright_white(), note.bright_black())
+}
+
+/// Set by `--currency` to convert displayed costs
+static CURRENCY: OnceLock<Currency> = OnceLock::new();
+
+/// Choose the currency costs are shown in for the rest of the process
+/// Only the first call takes effect; costs are shown in USD until then
+pub fn set_currency(currency: Currency) {
+    let _ = CURRENCY.set(currency);
+}
+
+/// The currency costs are shown in
+pub fn currency() -> &'static Currency {
+    CURRENCY.get_or_init(Currency::default)
+}
+
+/// Format a USD cost in the display currency with two decimals
+pub fn format_cost(usd: f64) -> String {
+    currency().format(usd, 2)
+}
+
+/// Format a USD cost difference with an explicit sign
+fn format_cost_delta(usd: f64) -> String {
+    format!("{}{}", if usd < 0.0 { "-" } else { "+" }, format_cost(usd.abs()))
+}
+
+/// Print an informational line to stdout unless quiet mode is on
+/// Use for progress and status chatter, not for results or errors
+pub fn info(message: impl std::fmt::Display) {
+    if !is_quiet() {
+        println!("{}", message);
+    }
+}
+
+/// Await `future` while showing a spinner with `message` on stderr
+/// Falls back to printing the message once when stderr isn't a terminal,
+/// and shows nothing at all in quiet mode
+pub async fn with_spinner<T>(message: &str, future: impl Future<Output = T>) -> T {
+    if is_quiet() || !std::io::stderr().is_terminal() {
+        info(message);
+        return future.await;
+    }
+
+    let spinner = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}").expect("valid spinner template"))
+        .with_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let result = future.await;
+    spinner.finish_and_clear();
+    result
+}
+
+/// When usage figures change color; see `Config::color_thresholds`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorThresholds {
+    /// Percent of the monthly limit at which usage turns yellow
+    pub warn_percent: f64,
+    /// Percent of the monthly limit at which usage turns bright yellow (red is always 100%)
+    pub critical_percent: f64,
+    /// History rows above this many tokens are yellow
+    pub warn_tokens: u64,
+    /// History rows above this many tokens are red
+    pub critical_tokens: u64,
+}
+
+impl Default for ColorThresholds {
+    fn default() -> Self {
+        Self { warn_percent: 50.0, critical_percent: 80.0, warn_tokens: 50_000, critical_tokens: 100_000 }
+    }
+}
+
+impl ColorThresholds {
+    /// Color a percentage-of-limit string by where `percentage` falls
+    fn color_percentage(&self, text: String, percentage: f64) -> ColoredString {
+        if percentage < self.warn_percent {
+            text.green()
+        } else if percentage < self.critical_percent {
+            text.yellow()
+        } else if percentage < 100.0 {
+            text.bright_yellow()
+        } else {
+            text.red().bold()
+        }
+    }
+
+    /// Color a token count string by where `total` falls
+    fn color_tokens(&self, text: String, total: u64) -> ColoredString {
+        if total > self.critical_tokens {
+            text.red()
+        } else if total > self.warn_tokens {
+            text.yellow()
+        } else {
+            text.white()
+        }
+    }
+}
 
 /// Display the status with colored output
-pub fn display_status(summary: &UsageSummary, monthly_limit: Option<u64>) {
-    println!("\n{}", "═".repeat(60).bright_blue());
+/// `daily` is today's usage and the daily limit it's measured against, when one is set
+pub fn display_status(
+    summary: &UsageSummary,
+    monthly_limit: Option<u64>,
+    daily: Option<(&UsageSummary, u64)>,
+    forecast: Option<&MonthForecast>,
+    bests: Option<&PersonalBests>,
+    pricing: &ModelPricing,
+    thresholds: ColorThresholds,
+) {
+    println!("\n{}", divider(60).bright_blue());
     println!("{}", "  TOKEN USAGE SUMMARY".bright_cyan().bold());
-    println!("{}", "═".repeat(60).bright_blue());
+    println!("{}", divider(60).bright_blue());
+
+    // Distinguish "query returned nothing" from genuinely zero usage
+    if summary.record_count == 0 {
+        println!("\n  {}", NO_RECORDS_HINT.yellow());
+    }
 
     // Display token counts
     println!("\n{}", "Token Counts:".bright_white().bold());
-    println!("  {} {}", "Input tokens: ".cyan(), format_number(summary.total_input_tokens).bright_white());
-    println!("  {} {}", "Output tokens:".cyan(), format_number(summary.total_output_tokens).bright_white());
-    println!("  {} {}", "Total tokens: ".cyan().bold(), format_number(summary.total_tokens).bright_yellow().bold());
+    println!("  {} {}", "Input tokens:       ".cyan(), format_number(summary.total_input_tokens).bright_white());
+    println!("  {} {}", "Output tokens:      ".cyan(), format_number(summary.total_output_tokens).bright_white());
+    println!("  {} {}", "Cache create tokens:".cyan(), format_number(summary.total_cache_creation_tokens).bright_white());
+    println!("  {} {}", "Cache read tokens:  ".cyan(), format_number(summary.total_cache_read_tokens).bright_white());
+    if summary.total_reasoning_tokens > 0 {
+        println!("  {} {}", "Reasoning tokens:   ".cyan(), format_number(summary.total_reasoning_tokens).bright_white());
+    }
+
+    // Both totals are shown; the one counted against the quota is highlighted
+    let (io_tokens, all_tokens) = (format_number(summary.io_tokens()), format_number(summary.all_tokens()));
+    if summary.includes_cache {
+        println!("  {} {}", "I/O tokens:         ".cyan(), io_tokens.bright_white());
+        println!("  {} {}", "All tokens:         ".cyan().bold(), all_tokens.bright_yellow().bold());
+    } else {
+        println!("  {} {}", "I/O tokens:         ".cyan().bold(), io_tokens.bright_yellow().bold());
+        println!("  {} {}", "All tokens:         ".cyan(), all_tokens.bright_white());
+    }
+    if shows_effective_tokens() {
+        let effective = format_effective_tokens(
+            pricing,
+            summary.total_input_tokens,
+            summary.total_cache_creation_tokens,
+            summary.total_cache_read_tokens,
+        );
+        println!("  {} {}", "Effective input:    ".cyan(), effective);
+    }
 
     // Display usage stats
     println!("\n{}", "Usage Stats:".bright_white().bold());
     println!("  {} {}", "Days with usage:".cyan(), summary.days_with_usage.to_string().bright_white());
+    if let Some(average) = summary.daily_average() {
+        println!("  {} {}", "Daily average:  ".cyan(), format_number(average.round() as u64).bright_white());
+    }
+
+    // Straight-line projection to month end, red once it would overshoot the limit
+    let days_in_month = models::days_in_month(chrono::Local::now().date_naive());
+    if let Some(projected) = summary.projected_month_total(days_in_month) {
+        let projected_display = format_number(projected);
+        let colored_projection = match monthly_limit {
+            Some(limit) if projected > limit => projected_display.red().bold(),
+            Some(_) => projected_display.green(),
+            None => projected_display.bright_white(),
+        };
+        println!("  {} {}", "Month-end proj.:".cyan(), colored_projection);
+    }
 
-    // If monthly limit is provided, show progress
+    // Weekday-aware forecast with `--forecast`, red when even its low end overshoots the limit
+    match forecast {
+        Some(MonthForecast::Trend(forecast)) => {
+            let projected = format_number(forecast.projected);
+            let colored = match monthly_limit {
+                Some(limit) if forecast.low > limit => projected.red().bold(),
+                Some(limit) if forecast.high > limit => projected.yellow(),
+                Some(_) => projected.green(),
+                None => projected.bright_white(),
+            };
+            println!("  {} {} {}",
+                "Trend forecast: ".cyan(),
+                colored,
+                format!("(likely {} – {})", format_number(forecast.low), format_number(forecast.high)).bright_black()
+            );
+        }
+        Some(MonthForecast::TooLittleHistory { days }) => {
+            println!("  {} {}",
+                "Trend forecast: ".cyan(),
+                format!("needs {} days of history, have {}; using the projection above", models::MIN_FORECAST_DAYS, days).bright_black()
+            );
+        }
+        None => {}
+    }
+
+    // Display estimated cost
+    println!("\n{}", "Estimated Cost:".bright_white().bold());
+    println!("  {} {}", "Total cost:     ".cyan(), format_cost(summary.estimated_cost(pricing)).bright_green());
+    println!("  {} {}", "Cache hit ratio:".cyan(), format_ratio(summary.cache_hit_ratio()).bright_white());
+    println!("  {} {}", "Cache savings:  ".cyan(), format_cost(summary.cache_savings(pricing)).bright_green());
+
+    // Show progress against each limit that's set
     if let Some(limit) = monthly_limit {
-        let percentage = summary.percentage_used(limit);
-        let remaining = summary.remaining(limit);
+        display_quota("Monthly Quota:", summary, limit, thresholds);
+    }
+    if let Some((today, limit)) = daily {
+        display_quota("Daily Quota (today, UTC):", today, limit, thresholds);
+    }
 
-        println!("\n{}", "Monthly Quota:".bright_white().bold());
-        println!("  {} {}", "Limit:       ".cyan(), format_number(limit).bright_white());
-        println!("  {} {}", "Used:        ".cyan(), format_number(summary.total_tokens).bright_yellow());
+    if let Some(bests) = bests {
+        display_personal_bests(bests);
+    }
 
-        if remaining >= 0 {
-            println!("  {} {}", "Remaining:   ".cyan(), format_number(remaining as u64).bright_green());
-        } else {
-            println!("  {} {}", "Overage:     ".cyan(), format_number(remaining.abs() as u64).bright_red());
-        }
+    println!("\n{}", divider(60).bright_blue());
+}
 
-        // Display percentage with color coding
-        let percentage_display = format!("{:.1}%", percentage);
-        let colored_percentage = if percentage < 50.0 {
-            percentage_display.green()
-        } else if percentage < 80.0 {
-            percentage_display.yellow()
-        } else if percentage < 100.0 {
-            percentage_display.bright_yellow()
+/// Print the all-time peaks remembered across runs, if any have been seen yet
+fn display_personal_bests(bests: &PersonalBests) {
+    if bests.peak_day_tokens.is_none() && bests.peak_month_cost.is_none() {
+        return;
+    }
+
+    println!("\n{}", "Personal Bests:".bright_white().bold());
+    if let Some(day) = &bests.peak_day_tokens {
+        println!("  {} {} {}", "Busiest day:    ".cyan(), format_number(day.value).bright_magenta(), format!("tokens on {}", day.period).bright_black());
+    }
+    if let Some(month) = &bests.peak_month_cost {
+        println!("  {} {} {}", "Priciest month: ".cyan(), format_cost(month.value).bright_magenta(), format!("in {}", month.period).bright_black());
+    }
+}
+
+/// One-line status for shell prompts, e.g. "claude: 2.3M/5M (46%) $7.12"
+/// Counts are always abbreviated; the percentage is colored only when `color` is set
+pub fn format_compact_status(
+    summary: &UsageSummary,
+    limit: u64,
+    pricing: &ModelPricing,
+    thresholds: ColorThresholds,
+    color: bool,
+) -> String {
+    let numbers = NumberFormat { abbreviate: true, ..util::number_format().clone() };
+    let percentage = summary.percentage_used(limit);
+    let percent = format!("({:.0}%)", percentage);
+    let percent = if color { thresholds.color_percentage(percent, percentage).to_string() } else { percent };
+
+    format!(
+        "claude: {}/{} {} {}",
+        numbers.format(summary.total_tokens),
+        numbers.format(limit),
+        percent,
+        format_cost(summary.estimated_cost(pricing))
+    )
+}
+
+/// Print usage against one limit, with a progress bar
+fn display_quota(title: &str, summary: &UsageSummary, limit: u64, thresholds: ColorThresholds) {
+    let percentage = summary.percentage_used(limit);
+    let remaining = summary.remaining(limit);
+
+    println!("\n{}", title.bright_white().bold());
+    println!("  {} {}", "Limit:       ".cyan(), format_number(limit).bright_white());
+    let basis = if summary.includes_cache { "(all tokens)" } else { "(I/O tokens)" };
+    println!("  {} {} {}", "Used:        ".cyan(), format_number(summary.total_tokens).bright_yellow(), basis.bright_black());
+
+    if remaining >= 0 {
+        println!("  {} {}", "Remaining:   ".cyan(), format_signed_number(remaining).bright_green());
+    } else {
+        println!("  {} {}", "Overage:     ".cyan(), format_signed_number(remaining.abs()).bright_red());
+    }
+
+    // Display percentage with color coding
+    let colored_percentage = thresholds.color_percentage(format!("{:.1}%", percentage), percentage);
+    println!("  {} {}", "Usage:       ".cyan(), colored_percentage);
+
+    // Display progress bar
+    display_progress_bar(percentage, thresholds);
+}
+
+/// Plain-text usage report for pasting into a standup or email
+/// `previous` is the period just before `current`; `top_project` comes from local logs when available
+pub fn format_report(
+    period: ReportPeriod,
+    current: &UsageSummary,
+    previous: &UsageSummary,
+    top_project: Option<(&str, u64)>,
+    pricing: &ModelPricing,
+) -> String {
+    let noun = period.noun();
+    let cost = current.estimated_cost(pricing);
+    let mut prose = format!(
+        "This {} you used {} tokens ({})",
+        noun,
+        format_number(current.total_tokens),
+        format_cost(cost)
+    );
+
+    if previous.total_tokens == 0 {
+        prose.push_str(&format!("; there's no usage from the previous {} to compare against.", noun));
+    } else {
+        let change = (current.total_tokens as f64 - previous.total_tokens as f64) / previous.total_tokens as f64 * 100.0;
+        let direction = if change.abs() < 0.05 {
+            "about the same as".to_string()
+        } else if change > 0.0 {
+            format!("up {:.1}% from", change)
         } else {
-            percentage_display.red().bold()
+            format!("down {:.1}% from", change.abs())
         };
+        prose.push_str(&format!(", {} last {}.", direction, noun));
+    }
 
-        println!("  {} {}", "Usage:       ".cyan(), colored_percentage);
+    if let Some((project, tokens)) = top_project {
+        prose.push_str(&format!(" Top project was {} with {} tokens.", project, format_number(tokens)));
+    }
 
-        // Display progress bar
-        display_progress_bar(percentage);
+    let mut report = prose;
+    report.push_str("\n\n");
+    report.push_str(&format!(
+        "{:<10} {:>14} {:>14} {:>14} {:>10}\n",
+        "Period", "Input", "Output", "I/O total", "Cost"
+    ));
+    for (label, summary) in [(format!("This {}", noun), current), (format!("Last {}", noun), previous)] {
+        report.push_str(&format!(
+            "{:<10} {:>14} {:>14} {:>14} {:>10}\n",
+            label,
+            format_number(summary.total_input_tokens),
+            format_number(summary.total_output_tokens),
+            format_number(summary.io_tokens()),
+            format_cost(summary.estimated_cost(pricing))
+        ));
     }
 
-    println!("\n{}", "═".repeat(60).bright_blue());
+    report
+}
+
+/// Banner shown by `status --watch` while backing off from a 429
+pub fn display_rate_limited(retry_in: u64) {
+    println!("\n{}", divider(60).bright_blue());
+    println!("{}", "  RATE LIMITED".bright_yellow().bold());
+    println!("{}", divider(60).bright_blue());
+    println!("\n  {}", format!("Anthropic API rate limit hit, retrying in {}s", retry_in).yellow());
+    println!("\n{}", divider(60).bright_blue());
+}
+
+/// Progress bar width when the terminal size is unknown
+const DEFAULT_BAR_WIDTH: usize = 40;
+
+/// Narrowest a divider or progress bar is shrunk to on small terminals
+const MIN_RULE_WIDTH: usize = 20;
+
+/// Widest the progress bar grows on large terminals
+const MAX_BAR_WIDTH: usize = 80;
+
+/// Width of the terminal stdout is attached to, or None when it isn't one
+fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size().ok().map(|(columns, _)| usize::from(columns))
+}
+
+/// Shrink a preferred line width to fit the terminal (never below `MIN_RULE_WIDTH`)
+fn fit_width(preferred: usize, terminal: Option<usize>) -> usize {
+    match terminal {
+        Some(columns) => preferred.min(columns.max(MIN_RULE_WIDTH)),
+        None => preferred,
+    }
+}
+
+/// A `═` divider line of up to `width` characters, shortened on narrow terminals
+pub fn divider(width: usize) -> String {
+    "═".repeat(fit_width(width, terminal_width()))
+}
+
+/// A `─` separator line of up to `width` characters, shortened on narrow terminals
+pub fn separator(width: usize) -> String {
+    "─".repeat(fit_width(width, terminal_width()))
+}
+
+/// Progress bar width: half the terminal, clamped, or the default off a terminal
+fn progress_bar_width(terminal: Option<usize>) -> usize {
+    terminal.map_or(DEFAULT_BAR_WIDTH, |columns| (columns / 2).clamp(MIN_RULE_WIDTH, MAX_BAR_WIDTH))
 }
 
 /// Display a progress bar for usage percentage
-fn display_progress_bar(percentage: f64) {
-    let bar_width = 40;
+fn display_progress_bar(percentage: f64, thresholds: ColorThresholds) {
+    let bar_width = progress_bar_width(terminal_width());
     let filled = ((percentage / 100.0) * bar_width as f64) as usize;
     let filled = filled.min(bar_width);
 
@@ -72,81 +447,769 @@ fn display_progress_bar(percentage: f64) {
     bar.push(']');
 
     // Color the bar based on usage
-    let colored_bar = if percentage < 50.0 {
-        bar.green()
-    } else if percentage < 80.0 {
-        bar.yellow()
-    } else if percentage < 100.0 {
-        bar.bright_yellow()
-    } else {
-        bar.red().bold()
-    };
+    println!("{}", thresholds.color_percentage(bar, percentage));
+}
+
+/// Optional extra columns for the history table
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HistoryColumns<'a> {
+    /// Show an estimated cost column using this pricing
+    pub cost: Option<&'a ModelPricing>,
+
+    /// Show each day's share of this monthly token limit
+    pub monthly_limit: Option<u64>,
+
+    /// Bucket size for each row (daily by default)
+    pub grouping: Grouping,
+
+    /// Show each bucket's full start and end time instead of just its date
+    pub show_times: bool,
 
-    println!("{}", colored_bar);
+    /// When row totals turn yellow and red
+    pub thresholds: ColorThresholds,
+
+    /// What rows are sorted by (date by default)
+    pub sort: SortKey,
+
+    /// Sort direction (newest or largest first by default)
+    pub order: SortOrder,
+
+    /// Leave out buckets the API returned with an empty `results` array
+    pub hide_empty: bool,
 }
 
-/// Format a number with thousand separators
-fn format_number(n: u64) -> String {
-    let s = n.to_string();
-    let mut result = String::new();
-    let mut count = 0;
+impl HistoryColumns<'_> {
+    /// Width of the table including any extra columns
+    fn table_width(&self) -> usize {
+        let mut width = 76 + self.label_width() - 12;
+        if self.cost.is_some() {
+            width += 13;
+        }
+        if self.monthly_limit.is_some() {
+            width += 13;
+        }
+        width
+    }
+
+    /// Width of the leading date (or time range) column
+    fn label_width(&self) -> usize {
+        if self.show_times { 30 } else { 12 }
+    }
+
+    /// Heading for the leading column
+    fn label_header(&self) -> &'static str {
+        if self.show_times { "Bucket (UTC)" } else { self.grouping.label() }
+    }
+
+    /// Rows to show, bucketed and sorted, plus how many empty buckets were hidden
+    fn rows(&self, records: &[UsageRecord], days: u32) -> (Vec<UsageRecord>, usize) {
+        // Drop empty buckets before grouping so they can't pad a week or month either
+        let (shown, hidden): (Vec<UsageRecord>, Vec<UsageRecord>) =
+            records.iter().cloned().partition(|record| !(self.hide_empty && record.is_empty()));
 
-    for c in s.chars().rev() {
-        if count == 3 {
-            result.insert(0, ',');
-            count = 0;
+        // Bucket and sort records (newest first unless --sort/--order say otherwise)
+        let mut rows = self.grouping.group_records(&shown);
+        models::sort_records(&mut rows, self.sort, self.order);
+
+        // Sub-daily buckets can outnumber days, so only cap the row count for the date view
+        if !self.show_times {
+            rows.truncate(days as usize);
         }
-        result.insert(0, c);
-        count += 1;
+        (rows, hidden.len())
     }
 
-    result
+    /// Share of the monthly limit used by `total`, as a percentage
+    fn limit_share(limit: u64, total: u64) -> f64 {
+        if limit == 0 { 0.0 } else { total as f64 / limit as f64 * 100.0 }
+    }
+
+    /// Text for a row's leading column
+    fn label(&self, record: &UsageRecord) -> String {
+        if self.show_times {
+            format_bucket_range(&record.starting_at, &record.ending_at)
+        } else {
+            self.grouping.bucket_key(&record.date())
+        }
+    }
+}
+
+/// Format a bucket's boundaries as `YYYY-MM-DD HH:MM → HH:MM` in UTC,
+/// repeating the date on the end when it falls on a different day
+/// Timestamps that don't parse are shown as-is
+fn format_bucket_range(starting_at: &str, ending_at: &str) -> String {
+    use chrono::{DateTime, Utc};
+
+    let start = DateTime::parse_from_rfc3339(starting_at).map(|t| t.with_timezone(&Utc));
+    let end = DateTime::parse_from_rfc3339(ending_at).map(|t| t.with_timezone(&Utc));
+    match (start, end) {
+        (Ok(start), Ok(end)) if start.date_naive() == end.date_naive() => {
+            format!("{} → {}", start.format("%Y-%m-%d %H:%M"), end.format("%H:%M"))
+        }
+        (Ok(start), Ok(end)) => {
+            format!("{} → {}", start.format("%Y-%m-%d %H:%M"), end.format("%m-%d %H:%M"))
+        }
+        _ => format!("{} → {}", starting_at, ending_at),
+    }
 }
 
 /// Display history of usage over time
-pub fn display_history(records: &[UsageRecord], days: u32) {
-    println!("\n{}", "═".repeat(80).bright_blue());
-    println!("{}", format!("  USAGE HISTORY - Last {} Days", days).bright_cyan().bold());
-    println!("{}", "═".repeat(80).bright_blue());
+pub fn display_history(records: &[UsageRecord], days: u32, columns: HistoryColumns, bests: Option<&PersonalBests>) {
+    let table_width = columns.table_width();
+
+    println!("\n{}", divider(table_width + 4).bright_blue());
+    let title = match columns.grouping {
+        Grouping::Day => format!("  USAGE HISTORY - Last {} Days", days),
+        Grouping::Week => format!("  WEEKLY USAGE HISTORY - Last {} Days", days),
+        Grouping::Month => format!("  MONTHLY USAGE HISTORY - Last {} Days", days),
+    };
+    println!("{}", title.bright_cyan().bold());
+    println!("{}", divider(table_width + 4).bright_blue());
 
     if records.is_empty() {
-        println!("\n  {}", "No usage data found for the specified period.".yellow());
-        println!("\n{}", "═".repeat(80).bright_blue());
+        println!("\n  {}", NO_RECORDS_HINT.yellow());
+        println!("\n{}", divider(table_width + 4).bright_blue());
         return;
     }
 
     // Header
-    println!("\n  {:<12} {:>15} {:>15} {:>15}",
-        "Date".cyan().bold(),
+    let mut header = format!("\n  {:<width$} {:>15} {:>15} {:>15}",
+        columns.label_header().cyan().bold(),
         "Input".cyan().bold(),
         "Output".cyan().bold(),
-        "Total".cyan().bold()
+        "Total".cyan().bold(),
+        width = columns.label_width()
     );
-    println!("  {}", "─".repeat(76).bright_black());
-
-    // Sort records by date and display
-    let mut sorted_records = records.to_vec();
-    sorted_records.sort_by(|a, b| b.date().cmp(&a.date()));
+    if columns.cost.is_some() {
+        header.push_str(&format!(" {:>12}", "Cost".cyan().bold()));
+    }
+    if columns.monthly_limit.is_some() {
+        header.push_str(&format!(" {:>12}", "% of Limit".cyan().bold()));
+    }
+    println!("{}", header);
+    println!("  {}", separator(table_width).bright_black());
 
-    for record in sorted_records.iter().take(days as usize) {
+    let (rows, hidden) = columns.rows(records, days);
+    for record in &rows {
         let total = record.total();
 
         // Color code based on usage
-        let total_colored = if total > 100_000 {
-            format_number(total).red()
-        } else if total > 50_000 {
-            format_number(total).yellow()
-        } else {
-            format_number(total).white()
-        };
+        let total_colored = columns.thresholds.color_tokens(format_number(total), total);
 
-        println!("  {:<12} {:>15} {:>15} {:>15}",
-            record.date().bright_white(),
+        let mut row = format!("  {:<width$} {:>15} {:>15} {:>15}",
+            columns.label(record).bright_white(),
             format_number(record.input_tokens()).white(),
             format_number(record.output_tokens()).white(),
-            total_colored
+            total_colored,
+            width = columns.label_width()
         );
+        if let Some(pricing) = columns.cost {
+            row.push_str(&format!(" {:>12}", format_cost(record.estimated_cost(pricing)).bright_green()));
+        }
+        if let Some(limit) = columns.monthly_limit {
+            row.push_str(&format!(" {:>12}", format!("{:.2}%", HistoryColumns::limit_share(limit, total)).white()));
+        }
+        println!("{}", row);
     }
 
-    println!("\n{}", "═".repeat(80).bright_blue());
+    if hidden > 0 {
+        println!("\n  {}", format!("{} empty bucket(s) hidden; pass --show-empty to show them", hidden).bright_black());
+    }
+    if let Some(bests) = bests {
+        display_personal_bests(bests);
+    }
+    println!("\n{}", divider(table_width + 4).bright_blue());
+}
+
+/// Render the history table as GitHub-flavored Markdown, without colors
+/// Rows are bucketed, sorted and filtered exactly as in `display_history`
+pub fn format_history_markdown(records: &[UsageRecord], days: u32, columns: HistoryColumns) -> String {
+    let (rows, _) = columns.rows(records, days);
+    if rows.is_empty() {
+        return format!("{}\n", NO_RECORDS_HINT);
+    }
+
+    let mut header = vec![columns.label_header(), "Input", "Output", "Total"];
+    let mut separator = vec!["---", "---:", "---:", "---:"];
+    if columns.cost.is_some() {
+        header.push("Cost");
+        separator.push("---:");
+    }
+    if columns.monthly_limit.is_some() {
+        header.push("% of Limit");
+        separator.push("---:");
+    }
+
+    let mut table = format!("| {} |\n|{}|\n", header.join(" | "), separator.join("|"));
+    for record in &rows {
+        let total = record.total();
+        let mut cells = vec![
+            columns.label(record),
+            format_number(record.input_tokens()),
+            format_number(record.output_tokens()),
+            format_number(total),
+        ];
+        if let Some(pricing) = columns.cost {
+            cells.push(format_cost(record.estimated_cost(pricing)));
+        }
+        if let Some(limit) = columns.monthly_limit {
+            cells.push(format!("{:.2}%", HistoryColumns::limit_share(limit, total)));
+        }
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    table
+}
+
+/// Shades for heatmap cells, from no usage to the busiest day
+const HEATMAP_SHADES: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+
+/// Display a GitHub-style heatmap: one column per ISO week, one row per weekday
+/// Days without a record (or with zero usage) show as empty cells
+pub fn display_heatmap(records: &[UsageRecord], days: u32) {
+    use chrono::{Datelike, NaiveDate};
+    use std::collections::HashMap;
+
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  USAGE HEATMAP - Last {} Days", days).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    // Daily totals keyed by (UTC) date; records with unparseable timestamps are skipped
+    let mut totals: HashMap<NaiveDate, u64> = HashMap::new();
+    for record in records {
+        if let Some(start) = record.date_time() {
+            *totals.entry(start.date_naive()).or_default() += record.total();
+        }
+    }
+
+    let (Some(first), Some(last)) = (totals.keys().min().copied(), totals.keys().max().copied()) else {
+        println!("\n  {}", NO_RECORDS_HINT.yellow());
+        println!("\n{}", divider(80).bright_blue());
+        return;
+    };
+
+    // Every week from the first to the last record, so gaps show as empty columns
+    let first_monday = first - chrono::Duration::days(i64::from(first.weekday().num_days_from_monday()));
+    let weeks: Vec<NaiveDate> = first_monday
+        .iter_weeks()
+        .take_while(|monday| *monday <= last)
+        .collect();
+    let max = totals.values().copied().max().unwrap_or(0);
+
+    // Week labels above the grid, every fourth column to leave room for them
+    let mut labels = String::new();
+    for (i, monday) in weeks.iter().enumerate() {
+        if i % 4 == 0 {
+            labels.push_str(&format!("{:<8}", format!("W{:02}", monday.iso_week().week())));
+        }
+    }
+    println!("\n       {}", labels.trim_end().bright_black());
+
+    for (row, weekday) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].iter().enumerate() {
+        let mut line = format!("  {:<4} ", weekday.cyan());
+        for monday in &weeks {
+            let date = *monday + chrono::Duration::days(row as i64);
+            let total = totals.get(&date).copied().unwrap_or(0);
+            line.push_str(&format!("{} ", heatmap_cell(total, max)));
+        }
+        println!("{}", line);
+    }
+
+    // Legend
+    let legend: Vec<String> = (0..HEATMAP_SHADES.len())
+        .map(|level| shade(level).to_string())
+        .collect();
+    println!("\n  {} {} {}   {} {}",
+        "Less".bright_black(),
+        legend.join(" "),
+        "More".bright_black(),
+        "Busiest day:".bright_black(),
+        format_number(max).bright_white()
+    );
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Shade a day's total relative to the busiest day, on a five-step ramp
+fn heatmap_cell(total: u64, max: u64) -> ColoredString {
+    if total == 0 || max == 0 {
+        return shade(0);
+    }
+    let level = ((total as f64 / max as f64) * 4.0).ceil() as usize;
+    shade(level.clamp(1, HEATMAP_SHADES.len() - 1))
+}
+
+/// Colored block for one heatmap intensity level
+fn shade(level: usize) -> ColoredString {
+    let block = HEATMAP_SHADES[level];
+    match level {
+        0 => block.bright_black(),
+        1 => block.green(),
+        2 => block.bright_green(),
+        3 => block.yellow(),
+        _ => block.red(),
+    }
+}
+
+/// Display the N highest-usage days sorted by total tokens descending
+pub fn display_top_days(records: &[UsageRecord], top: usize, pricing: &ModelPricing) {
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  TOP {} DAYS BY USAGE", top).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    if records.is_empty() {
+        println!("\n  {}", NO_RECORDS_HINT.yellow());
+        println!("\n{}", divider(80).bright_blue());
+        return;
+    }
+
+    // Header
+    println!("\n  {:<6} {:<12} {:>15} {:>15} {:>12}",
+        "Rank".cyan().bold(),
+        "Date".cyan().bold(),
+        "Total".cyan().bold(),
+        "Output".cyan().bold(),
+        "Cost".cyan().bold()
+    );
+    println!("  {}", separator(76).bright_black());
+
+    let mut sorted_records = records.to_vec();
+    sorted_records.sort_by_key(|r| std::cmp::Reverse(r.total()));
+
+    for (rank, record) in sorted_records.iter().take(top).enumerate() {
+        println!("  {:<6} {:<12} {:>15} {:>15} {:>12}",
+            format!("#{}", rank + 1).bright_white(),
+            record.date().bright_white(),
+            format_number(record.total()).bright_yellow(),
+            format_number(record.output_tokens()).white(),
+            format_cost(record.estimated_cost(pricing)).bright_green()
+        );
+    }
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Print an estimated token count for some text and what it would cost as input to `model`
+pub fn display_token_estimate(characters: usize, tokens: u64, model: &str, cost: f64) {
+    println!("\n{}", divider(60).bright_blue());
+    println!("{}", "  TOKEN ESTIMATE".bright_cyan().bold());
+    println!("{}", divider(60).bright_blue());
+
+    println!("\n  {} {}", "Characters:     ".cyan(), format_number(characters as u64).bright_white());
+    println!("  {} {}", "Tokens (est.):  ".cyan(), format!("~{}", format_number(tokens)).bright_yellow().bold());
+    // Single prompts cost fractions of a cent, so show more decimals than elsewhere
+    println!("  {} {} {}", "Input cost:     ".cyan(), currency().format(cost, 6).bright_green(), format!("({})", model).bright_black());
+
+    println!(
+        "\n  {}",
+        "This is a heuristic estimate, not Claude's tokenizer; the server-side count can differ.".yellow()
+    );
+    println!("\n{}", divider(60).bright_blue());
+}
+
+/// Print the `limit` largest projects, with each one's share of the grand total
+/// `projects` must already be ranked (see `models::rank_projects`)
+pub fn display_top_projects(projects: &[ProjectTotal], limit: usize, by: RankBy, thresholds: &ColorThresholds) {
+    let noun = match by {
+        RankBy::Tokens => "TOKENS",
+        RankBy::Cost => "COST",
+    };
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  TOP {} PROJECTS BY {}", limit, noun).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    if projects.is_empty() {
+        println!("\n  {}", "No usage found in local Claude Code logs.".yellow());
+        println!("\n{}", divider(80).bright_blue());
+        return;
+    }
+
+    // The monthly-limit column only appears when some shown project has a limit
+    let shown = &projects[..limit.min(projects.len())];
+    let has_limits = shown.iter().any(|p| p.monthly_limit.is_some());
+
+    print!("\n  {:<6} {:<36} {:>15} {:>12} {:>7}",
+        "Rank".cyan().bold(),
+        "Project".cyan().bold(),
+        "Total".cyan().bold(),
+        "Cost".cyan().bold(),
+        "Share".cyan().bold()
+    );
+    if has_limits {
+        print!("  {}", "Month vs limit".cyan().bold());
+    }
+    println!();
+    println!("  {}", separator(if has_limits { 95 } else { 76 }).bright_black());
+
+    let grand_total: f64 = projects.iter().map(|p| p.value(by)).sum();
+    for (rank, project) in shown.iter().enumerate() {
+        let share = if grand_total == 0.0 { 0.0 } else { project.value(by) / grand_total * 100.0 };
+        print!("  {:<6} {:<36} {:>15} {:>12} {:>6.1}%",
+            format!("#{}", rank + 1).bright_white(),
+            project.name.bright_white(),
+            format_number(project.tokens).bright_yellow(),
+            format_cost(project.cost).bright_green(),
+            share
+        );
+        if let Some(monthly_limit) = project.monthly_limit.filter(|&l| l > 0) {
+            let percentage = project.month_tokens as f64 / monthly_limit as f64 * 100.0;
+            print!("  {}", thresholds.color_percentage(limit_bar(percentage), percentage));
+        }
+        println!();
+    }
+
+    if projects.len() > limit {
+        println!("\n  {}", format!("{} more project(s) not shown", projects.len() - limit).bright_black());
+    }
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Ten-cell bar and percentage for a project's share of its monthly limit, e.g. "███░░░░░░░  31%"
+fn limit_bar(percentage: f64) -> String {
+    let filled = ((percentage / 10.0).round() as usize).min(10);
+    format!("{}{} {:>4.0}%", "█".repeat(filled), "░".repeat(10 - filled), percentage)
+}
+
+/// Format the change from `a` to `b` as a percentage ("n/a" when `a` is zero)
+fn percent_change(a: f64, b: f64) -> String {
+    if a == 0.0 {
+        if b == 0.0 { "0.0%".to_string() } else { "n/a".to_string() }
+    } else {
+        format!("{:+.1}%", (b - a) / a * 100.0)
+    }
+}
+
+/// Color a delta: increases in red, decreases in green
+fn color_delta(text: String, delta: f64) -> ColoredString {
+    if delta > 0.0 {
+        text.red()
+    } else if delta < 0.0 {
+        text.green()
+    } else {
+        text.white()
+    }
+}
+
+/// Display a side-by-side comparison of two periods
+pub fn display_comparison(
+    (label_a, a): (&str, &UsageSummary),
+    (label_b, b): (&str, &UsageSummary),
+    pricing: &ModelPricing,
+) {
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  USAGE COMPARISON - {} vs {}", label_a, label_b).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    for (label, summary) in [(label_a, a), (label_b, b)] {
+        if summary.record_count == 0 {
+            println!("\n  {} {}", format!("{}:", label).yellow(), NO_RECORDS_HINT.yellow());
+        }
+    }
+
+    // Header
+    println!("\n  {:<14} {:>15} {:>15} {:>15} {:>10}",
+        "".cyan().bold(),
+        label_a.cyan().bold(),
+        label_b.cyan().bold(),
+        "Change".cyan().bold(),
+        "%".cyan().bold()
+    );
+    println!("  {}", separator(76).bright_black());
+
+    let token_rows = [
+        ("Input", a.total_input_tokens, b.total_input_tokens),
+        ("Output", a.total_output_tokens, b.total_output_tokens),
+        ("Cache create", a.total_cache_creation_tokens, b.total_cache_creation_tokens),
+        ("Cache read", a.total_cache_read_tokens, b.total_cache_read_tokens),
+        ("Reasoning", a.total_reasoning_tokens, b.total_reasoning_tokens),
+        ("I/O total", a.io_tokens(), b.io_tokens()),
+        ("All tokens", a.all_tokens(), b.all_tokens()),
+    ];
+
+    // Reasoning only gets a row when some model reported it
+    for (label, value_a, value_b) in token_rows.into_iter().filter(|&(label, a, b)| label != "Reasoning" || a + b > 0) {
+        let delta = value_b as i64 - value_a as i64;
+        println!("  {:<14} {:>15} {:>15} {:>15} {:>10}",
+            label.bright_white(),
+            format_number(value_a).white(),
+            format_number(value_b).white(),
+            color_delta(format!("{}{}", if delta > 0 { "+" } else { "" }, format_signed_number(delta)), delta as f64),
+            color_delta(percent_change(value_a as f64, value_b as f64), delta as f64)
+        );
+    }
+
+    let cost_a = a.estimated_cost(pricing);
+    let cost_b = b.estimated_cost(pricing);
+    let cost_delta = cost_b - cost_a;
+    println!("  {:<14} {:>15} {:>15} {:>15} {:>10}",
+        "Cost".bright_white(),
+        format_cost(cost_a).bright_green(),
+        format_cost(cost_b).bright_green(),
+        color_delta(format_cost_delta(cost_delta), cost_delta),
+        color_delta(percent_change(cost_a, cost_b), cost_delta)
+    );
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Display API-billed and locally-observed usage side by side
+/// The difference column is local minus API, so positive means logged usage not yet billed
+pub fn display_source_comparison(days: u32, api: Option<&SourceTotals>, local: Option<&SourceTotals>, pricing: &ModelPricing) {
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  USAGE SUMMARY - Last {} Days", days).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    // Header
+    println!("\n  {:<14} {:>15} {:>15} {:>15} {:>10}",
+        "".cyan().bold(),
+        "API (billed)".cyan().bold(),
+        "Local (logs)".cyan().bold(),
+        "Difference".cyan().bold(),
+        "%".cyan().bold()
+    );
+    println!("  {}", separator(76).bright_black());
+
+    // A side that failed to load shows as "—", and so do the columns comparing against it
+    let missing = || "—".bright_black();
+    let token_rows = [
+        ("Input", api.map(|t| t.input), local.map(|t| t.input)),
+        ("Output", api.map(|t| t.output), local.map(|t| t.output)),
+        ("Cache create", api.map(|t| t.cache_creation), local.map(|t| t.cache_creation)),
+        ("Cache read", api.map(|t| t.cache_read), local.map(|t| t.cache_read)),
+        ("Reasoning", api.map(|t| t.reasoning), local.map(|t| t.reasoning)),
+        ("All tokens", api.map(SourceTotals::total), local.map(SourceTotals::total)),
+    ];
+
+    // Reasoning only gets a row when some model reported it
+    let shown = token_rows.into_iter().filter(|&(label, a, b)| label != "Reasoning" || a.unwrap_or(0) + b.unwrap_or(0) > 0);
+    for (label, api_value, local_value) in shown {
+        let cell = |value: Option<u64>| value.map_or_else(missing, |v| format_number(v).white());
+        let (delta, percent) = match (api_value, local_value) {
+            (Some(api_value), Some(local_value)) => {
+                let delta = local_value as i64 - api_value as i64;
+                (
+                    color_delta(format!("{}{}", if delta > 0 { "+" } else { "" }, format_signed_number(delta)), delta as f64),
+                    color_delta(percent_change(api_value as f64, local_value as f64), delta as f64),
+                )
+            }
+            _ => (missing(), missing()),
+        };
+        println!("  {:<14} {:>15} {:>15} {:>15} {:>10}",
+            label.bright_white(),
+            cell(api_value),
+            cell(local_value),
+            delta,
+            percent
+        );
+    }
+
+    let api_cost = api.map(|api| api.estimated_cost(pricing));
+    let local_cost = local.map(|local| local.estimated_cost(pricing));
+    let cost_cell = |cost: Option<f64>| cost.map_or_else(missing, |cost| format_cost(cost).bright_green());
+    let (delta, percent) = match (api_cost, local_cost) {
+        (Some(api_cost), Some(local_cost)) => {
+            let cost_delta = local_cost - api_cost;
+            (
+                color_delta(format_cost_delta(cost_delta), cost_delta),
+                color_delta(percent_change(api_cost, local_cost), cost_delta),
+            )
+        }
+        _ => (missing(), missing()),
+    };
+    println!("  {:<14} {:>15} {:>15} {:>15} {:>10}",
+        "Cost".bright_white(),
+        cost_cell(api_cost),
+        cost_cell(local_cost),
+        delta,
+        percent
+    );
+
+    match (api, local) {
+        (Some(api), Some(local)) if local.total() > api.total() => {
+            println!("\n  {}", "Local logs show more usage than the API has billed; recent usage can take a few minutes to appear.".yellow());
+        }
+        (Some(api), Some(local)) if api.total() > local.total() => {
+            println!("\n  {}", "The API reports usage not found in local logs (other machines, other tools, or deleted logs).".yellow());
+        }
+        (None, _) => println!("\n  {}", "API usage couldn't be fetched, so only local logs are shown (see the warning above).".yellow()),
+        (_, None) => println!("\n  {}", "Local logs couldn't be read, so only API usage is shown (see the warning above).".yellow()),
+        _ => {}
+    }
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Display what Anthropic billed each day next to the cost estimated from usage
+pub fn display_cost_comparison(days: u32, rows: &[DailyCost]) {
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", format!("  BILLED COST - Last {} Days", days).bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+
+    if rows.is_empty() {
+        println!("\n  {}", "No cost records returned for this period — check your admin key and date range.".yellow());
+        println!("\n{}", divider(80).bright_blue());
+        return;
+    }
+
+    println!("\n  {:<14} {:>15} {:>15} {:>15} {:>10}",
+        "Date".cyan().bold(),
+        "Billed".cyan().bold(),
+        "Estimated".cyan().bold(),
+        "Difference".cyan().bold(),
+        "%".cyan().bold()
+    );
+    println!("  {}", separator(76).bright_black());
+
+    let total = DailyCost {
+        date: "Total".to_string(),
+        billed: rows.iter().map(|row| row.billed).sum(),
+        estimated: rows.iter().map(|row| row.estimated).sum(),
+    };
+    for (i, row) in rows.iter().chain(std::iter::once(&total)).enumerate() {
+        if i == rows.len() {
+            println!("  {}", separator(76).bright_black());
+        }
+        let delta = row.estimated - row.billed;
+        println!("  {:<14} {:>15} {:>15} {:>15} {:>10}",
+            row.date.bright_white(),
+            format_cost(row.billed).bright_green(),
+            format_cost(row.estimated).white(),
+            color_delta(format_cost_delta(delta), delta),
+            color_delta(percent_change(row.billed, row.estimated), delta)
+        );
+    }
+
+    println!("\n  {}", "Billed is what Anthropic charged the organization for every product; the estimate prices".bright_black());
+    println!("  {}", "only Claude Code usage at list rates, so some gap is expected.".bright_black());
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Display the `doctor` checklist with a fix under each failing item
+pub fn display_checklist(checks: &[Check]) {
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", "  SETUP CHECK".bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+    println!();
+
+    for check in checks {
+        let mark = match check.status {
+            CheckStatus::Pass => "✓".bright_green().bold(),
+            CheckStatus::Warn => "!".yellow().bold(),
+            CheckStatus::Fail => "✗".red().bold(),
+        };
+        println!("  {} {:<20} {}", mark, check.name.cyan(), check.detail.white());
+        if let Some(fix) = &check.fix {
+            println!("    {} {}", "→".bright_black(), fix.bright_black());
+        }
+    }
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+/// Print the version and where data is read from, as reported by `info`
+pub fn display_info(info: &Info) {
+    let not_found = |e: &String| format!("not found ({})", e).yellow().to_string();
+
+    println!("\n{}", divider(80).bright_blue());
+    println!("{}", "  CLAUDE TOKEN COUNTER".bright_cyan().bold());
+    println!("{}", divider(80).bright_blue());
+    println!();
+
+    println!("  {:<20} {}", "Version:".cyan(), info.version);
+    let config = match &info.config_path {
+        Ok((path, true)) => path.display().to_string(),
+        Ok((path, false)) => format!("{} {}", path.display(), "(not created yet)".bright_black()),
+        Err(e) => e.red().to_string(),
+    };
+    println!("  {:<20} {}", "Config file:".cyan(), config);
+    let api_key = match &info.api_key {
+        Ok(Some(key)) => key.clone(),
+        Ok(None) => "not configured".yellow().to_string(),
+        Err(e) => e.red().to_string(),
+    };
+    println!("  {:<20} {}", "API key:".cyan(), api_key);
+    let projects = info.projects_dir.as_ref().map_or_else(not_found, |dir| dir.display().to_string());
+    println!("  {:<20} {}", "Projects directory:".cyan(), projects);
+    println!("  {:<20} {}", "JSONL log files:".cyan(), format_number(info.log_files as u64));
+
+    println!("\n{}", divider(80).bright_blue());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_shrink_to_narrow_terminals_but_not_below_minimum() {
+        assert_eq!(fit_width(80, None), 80);
+        assert_eq!(fit_width(80, Some(200)), 80);
+        assert_eq!(fit_width(80, Some(50)), 50);
+        assert_eq!(fit_width(80, Some(5)), MIN_RULE_WIDTH);
+    }
+
+    #[test]
+    fn limit_bar_fills_by_tenths_and_caps_when_over() {
+        assert_eq!(limit_bar(0.0), "░░░░░░░░░░    0%");
+        assert_eq!(limit_bar(31.0), "███░░░░░░░   31%");
+        assert_eq!(limit_bar(250.0), "██████████  250%");
+    }
+
+    #[test]
+    fn progress_bar_scales_with_terminal_width() {
+        assert_eq!(progress_bar_width(None), DEFAULT_BAR_WIDTH);
+        assert_eq!(progress_bar_width(Some(100)), 50);
+        assert_eq!(progress_bar_width(Some(30)), MIN_RULE_WIDTH);
+        assert_eq!(progress_bar_width(Some(400)), MAX_BAR_WIDTH);
+    }
+
+    fn summary(input: u64, output: u64) -> UsageSummary {
+        let record: UsageRecord = serde_json::from_value(serde_json::json!({
+            "starting_at": "2025-01-06T00:00:00Z",
+            "ending_at": "2025-01-07T00:00:00Z",
+            "results": [{"input_tokens": input, "output_tokens": output}]
+        }))
+        .unwrap();
+        UsageSummary::from_records(&[record])
+    }
+
+    #[test]
+    fn history_markdown_is_a_plain_table() {
+        let record: UsageRecord = serde_json::from_value(serde_json::json!({
+            "starting_at": "2025-01-06T00:00:00Z",
+            "ending_at": "2025-01-07T00:00:00Z",
+            "results": [{"input_tokens": 1_234_567, "output_tokens": 1_000}]
+        }))
+        .unwrap();
+
+        let markdown = format_history_markdown(&[record], 30, HistoryColumns::default());
+        assert_eq!(
+            markdown,
+            "| Date | Input | Output | Total |\n|---|---:|---:|---:|\n| 2025-01-06 | 1,234,567 | 1,000 | 1,235,567 |\n"
+        );
+    }
+
+    #[test]
+    fn report_compares_with_previous_period() {
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+        let report = format_report(ReportPeriod::Week, &summary(1_000, 500), &summary(1_000, 0), Some(("my-repo", 900)), &pricing);
+
+        assert!(report.starts_with("This week you used 1,500 tokens ($0.01), up 50.0% from last week."));
+        assert!(report.contains("Top project was my-repo with 900 tokens."));
+        assert!(report.contains("Last week"));
+    }
+
+    #[test]
+    fn compact_status_is_one_plain_line() {
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+        let line = format_compact_status(&summary(2_000_000, 300_000), 5_000_000, &pricing, ColorThresholds::default(), false);
+
+        assert_eq!(line, "claude: 2.3M/5M (46%) $10.50");
+    }
+
+    #[test]
+    fn report_without_previous_usage_says_so() {
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+        let report = format_report(ReportPeriod::Month, &summary(10, 0), &UsageSummary::from_records(&[]), None, &pricing);
+
+        assert!(report.contains("there's no usage from the previous month to compare against."));
+        assert!(!report.contains("Top project"));
+    }
 }
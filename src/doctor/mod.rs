@@ -0,0 +1,274 @@
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config, Profile};
+use crate::local;
+
+/// Prefix shared by Admin API keys, the only keys the Usage API accepts
+pub const ADMIN_KEY_PREFIX: &str = "sk-ant-admin";
+
+/// Outcome of a single setup check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The check passed
+    Pass,
+    /// Something is missing, but only some commands need it
+    Warn,
+    /// Something is broken and `doctor` should exit non-zero
+    Fail,
+}
+
+/// One line of the `doctor` checklist
+#[derive(Debug, Clone)]
+pub struct Check {
+    /// What was checked
+    pub name: &'static str,
+    /// Whether it passed
+    pub status: CheckStatus,
+    /// What was found
+    pub detail: String,
+    /// How to fix it, when it didn't pass
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Run every setup check in order
+///
+/// API checks only warn, since local monitoring works without a key; a key
+/// that is present but not an Admin key fails, as do problems with the logs.
+pub fn run_checks(config_path: Option<&Path>, profile: Option<&str>, projects_dir: Option<&Path>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    check_config(config_path, profile, &mut checks);
+    check_logs(projects_dir, &mut checks);
+    checks
+}
+
+/// Whether any check failed outright
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.status == CheckStatus::Fail)
+}
+
+fn check_config(config_path: Option<&Path>, profile: Option<&str>, checks: &mut Vec<Check>) {
+    const CONFIG: &str = "Config file";
+    const API_KEY: &str = "Admin API key";
+
+    let path = match Config::config_path(config_path) {
+        Ok(path) => path,
+        Err(e) => {
+            checks.push(Check::fail(CONFIG, e.to_string(), "Pass --config with a writable path"));
+            return;
+        }
+    };
+
+    if !path.exists() {
+        checks.push(Check::warn(
+            CONFIG,
+            format!("not found at {}", path.display()),
+            "Run 'config --api-key YOUR_ADMIN_KEY' (only needed for status, history, compare, and budget)",
+        ));
+        checks.push(Check::warn(API_KEY, "no config file", "Configure a key to use the API commands"));
+        return;
+    }
+
+    let config = match Config::load(Some(&path)) {
+        Ok(config) => {
+            checks.push(Check::pass(CONFIG, path.display().to_string()));
+            config
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                CONFIG,
+                format!("{:#}", e),
+                format!("Fix or remove {} and run 'config --api-key' again", path.display()),
+            ));
+            return;
+        }
+    };
+
+    match config.profile(profile).and_then(|p| p.resolved()) {
+        Ok(p) if p.api_key.starts_with(ADMIN_KEY_PREFIX) => {
+            checks.push(Check::pass(API_KEY, p.masked_api_key()));
+        }
+        Ok(p) => checks.push(Check::fail(
+            API_KEY,
+            format!("{} is not an Admin key", p.masked_api_key()),
+            "Create an Admin key (sk-ant-admin-...) at https://console.anthropic.com/settings/keys",
+        )),
+        Err(e) => checks.push(Check::warn(API_KEY, e.to_string(), "Add a key with 'config --api-key'")),
+    }
+}
+
+fn check_logs(projects_dir: Option<&Path>, checks: &mut Vec<Check>) {
+    const PROJECTS_DIR: &str = "Projects directory";
+    const LOG_FILES: &str = "JSONL log files";
+    const PARSE: &str = "Log parsing";
+
+    let dir = match local::get_claude_projects_dir(projects_dir) {
+        Ok(dir) => {
+            checks.push(Check::pass(PROJECTS_DIR, dir.display().to_string()));
+            dir
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                PROJECTS_DIR,
+                e.to_string().lines().next().unwrap_or_default().to_string(),
+                format!("Use Claude Code at least once, or pass --projects-dir / set {}", local::PROJECTS_DIR_ENV),
+            ));
+            return;
+        }
+    };
+
+    let files = local::find_jsonl_files(&dir, &local::UsageFilter::default()).unwrap_or_default();
+    if files.is_empty() {
+        checks.push(Check::fail(
+            LOG_FILES,
+            "none found",
+            "Start a Claude Code session so it writes a conversation log",
+        ));
+        return;
+    }
+    checks.push(Check::pass(LOG_FILES, format!("{} found", files.len())));
+
+    // Dry-run the most recently modified file, since that's what live will read first
+    let newest = files
+        .iter()
+        .max_by_key(|f| f.metadata().and_then(|m| m.modified()).ok())
+        .expect("files is non-empty");
+
+    match dry_run_parse(newest) {
+        Ok((entries, 0)) => checks.push(Check::pass(
+            PARSE,
+            format!("{} usage entries in {}", entries, newest.display()),
+        )),
+        Ok((entries, malformed)) => checks.push(Check::warn(
+            PARSE,
+            format!("{} usage entries, {} malformed lines in {}", entries, malformed, newest.display()),
+            "Malformed lines are skipped; upgrade this tool if the log format changed",
+        )),
+        Err(e) => checks.push(Check::fail(
+            PARSE,
+            format!("{:#}", e),
+            "Check the file's permissions, or remove it if it is corrupt",
+        )),
+    }
+}
+
+/// Version and data locations shown by `info`, for pasting into bug reports
+#[derive(Debug, Clone)]
+pub struct Info {
+    /// Crate version this binary was built from
+    pub version: &'static str,
+    /// Config file that would be read, and whether it exists
+    pub config_path: Result<(PathBuf, bool), String>,
+    /// Masked API key and where it comes from, or None when no key is configured
+    pub api_key: Result<Option<String>, String>,
+    /// Claude Code projects directory that would be read
+    pub projects_dir: Result<PathBuf, String>,
+    /// JSONL log files found under the projects directory
+    pub log_files: usize,
+}
+
+/// Resolve everything `info` reports, the same way the other commands would
+/// Unlike `run_checks`, nothing here is judged; failures are kept as messages
+pub fn gather_info(config_path: Option<&Path>, profile: Option<&str>, api_key_file: Option<&Path>, projects_dir: Option<&Path>) -> Info {
+    let resolved_config = Config::config_path(config_path).map_err(|e| e.to_string());
+    let config_path = resolved_config.clone().map(|path| {
+        let exists = path.exists();
+        (path, exists)
+    });
+
+    let api_key = match config::api_key_override(api_key_file) {
+        Ok(Some(key)) => {
+            let source = if api_key_file.is_some() { "--api-key-file".to_string() } else { config::API_KEY_ENV.to_string() };
+            Ok(Some(format!("{} (from {})", Profile::new(key).masked_api_key(), source)))
+        }
+        Ok(None) => match resolved_config {
+            Ok(path) if path.exists() => match Config::load(Some(&path)) {
+                Ok(config) => Ok(config.profile(profile).ok().map(|p| {
+                    format!("{} (profile \"{}\")", p.masked_api_key(), config.profile_name(profile))
+                })),
+                Err(e) => Err(format!("{:#}", e)),
+            },
+            _ => Ok(None),
+        },
+        Err(e) => Err(format!("{:#}", e)),
+    };
+
+    let projects_dir = local::get_claude_projects_dir(projects_dir)
+        .map_err(|e| e.to_string().lines().next().unwrap_or_default().to_string());
+    let log_files = match &projects_dir {
+        Ok(dir) => local::find_jsonl_files(dir, &local::UsageFilter::default()).map_or(0, |files| files.len()),
+        Err(_) => 0,
+    };
+
+    Info { version: env!("CARGO_PKG_VERSION"), config_path, api_key, projects_dir, log_files }
+}
+
+/// Count usage entries and malformed lines in one log file
+fn dry_run_parse(path: &Path) -> anyhow::Result<(usize, usize)> {
+    let reader = local::open_log_reader(path)?;
+    let mut entries = 0;
+    let mut malformed = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match local::parse_log_line(&line) {
+            Ok(Some(entry)) if entry.message.as_ref().is_some_and(|m| m.usage.is_some()) => entries += 1,
+            Ok(_) => {}
+            Err(_) => malformed += 1,
+        }
+    }
+
+    Ok((entries, malformed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dry_run_counts_usage_entries_and_malformed_lines() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/local/malformed.jsonl");
+        let (entries, malformed) = dry_run_parse(&path).unwrap();
+
+        assert_eq!(entries, 2);
+        assert!(malformed > 0);
+    }
+
+    #[test]
+    fn info_resolves_paths_and_counts_logs() {
+        let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/local");
+        let config = Path::new("/nonexistent/claude-token-counter/config.toml");
+        let info = gather_info(Some(config), None, None, Some(&projects));
+
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.config_path, Ok((config.to_path_buf(), false)));
+        assert_eq!(info.projects_dir, Ok(projects));
+        assert!(info.log_files > 0);
+    }
+
+    #[test]
+    fn missing_projects_dir_is_a_failure() {
+        let mut checks = Vec::new();
+        check_logs(Some(Path::new("/nonexistent/claude-projects")), &mut checks);
+
+        assert!(has_failures(&checks));
+        assert!(checks[0].fix.is_some());
+    }
+}
@@ -0,0 +1,52 @@
+//! Claude token usage tracking.
+//!
+//! This crate powers the `claude-token-counter` CLI and can also be used as a
+//! library to build custom dashboards:
+//!
+//! - [`local`] parses Claude Code JSONL logs from `~/.claude/projects/` and
+//!   aggregates token usage (works for everyone, no API key needed).
+//! - [`api`] talks to the Anthropic Usage API (requires an Admin API key).
+//! - [`models`] holds the API response types and [`UsageSummary`].
+//! - [`pricing`] maps model names to [`ModelPricing`] and estimates cost.
+//!
+//! ```no_run
+//! use claude_token_counter::{get_claude_projects_dir, parse_all_files, pricing_for, UsageFilter};
+//!
+//! let projects_dir = get_claude_projects_dir(None)?;
+//! let usage = parse_all_files(&projects_dir, &UsageFilter::default())?;
+//! let cost = usage.estimated_cost(&pricing_for("claude-sonnet-4-5"));
+//! println!("{} tokens, ${:.2}", usage.total(), cost);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+/// Anthropic Usage API client
+pub mod api;
+/// On-disk cache of API usage responses
+pub mod cache;
+/// Config file with API key profiles and pricing overrides
+pub mod config;
+/// Colored terminal output used by the CLI
+pub mod display;
+/// Setup health checks behind the `doctor` command
+pub mod doctor;
+/// Offline token-count estimates for arbitrary text
+pub mod estimate;
+/// Claude Code JSONL log discovery, parsing, and aggregation
+pub mod local;
+/// Stderr output for `tracing` warnings and `-v` diagnostics
+pub mod logging;
+/// Prometheus text-format export of local usage
+pub mod metrics;
+/// API response types and usage summaries
+pub mod models;
+/// Per-model pricing and cost math
+pub mod pricing;
+/// Interactive ratatui live view
+pub mod tui;
+/// Shared formatting helpers
+pub mod util;
+
+pub use api::{AnthropicClient, ApiError};
+pub use local::*;
+pub use models::*;
+pub use pricing::{calculate_cost, pricing_for, resolve_pricing, ModelPricing, DEFAULT_MODEL};
@@ -1,10 +1,23 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use flate2::read::GzDecoder;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
 use walkdir::WalkDir;
 
+use crate::pricing::{ModelPricing, ServiceTier};
+use crate::util::format_number;
+
 /// Claude Code JSONL log entry
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LogEntry {
@@ -13,6 +26,8 @@ pub struct LogEntry {
     pub timestamp: Option<String>,
     #[serde(rename = "agentId")]
     pub agent_id: Option<String>,
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
 }
 
 /// Message structure from Claude Code logs
@@ -23,142 +38,1733 @@ pub struct Message {
 }
 
 /// Token usage information
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct Usage {
     #[serde(default)]
     pub input_tokens: u64,
     #[serde(default)]
     pub output_tokens: u64,
+    /// All cache writes, both the 5-minute and 1-hour kinds
     #[serde(default)]
     pub cache_creation_input_tokens: u64,
+    /// Split of the cache writes by cache lifetime, when the log records it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation: Option<CacheCreation>,
     #[serde(default)]
     pub cache_read_input_tokens: u64,
+    /// Reasoning (thinking) tokens reported apart from `output_tokens`
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+    /// Billing tier (`standard`, `batch`, `priority`); absent means standard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+}
+
+/// Cache writes broken down by how long the cache entry lives
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct CacheCreation {
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
 }
 
 impl Usage {
+    /// Total tokens across all token kinds
     pub fn total(&self) -> u64 {
-        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+            + self.reasoning_tokens
+    }
+
+    /// Service tier these tokens were billed under
+    pub fn tier(&self) -> ServiceTier {
+        ServiceTier::from_name(self.service_tier.as_deref())
+    }
+
+    /// One-hour cache writes; zero when the log doesn't break cache writes down
+    pub fn cache_creation_1h(&self) -> u64 {
+        self.cache_creation.as_ref().map_or(0, |c| c.ephemeral_1h_input_tokens)
+    }
+
+    /// Five-minute cache writes: every cache write that isn't a one-hour one
+    pub fn cache_creation_5m(&self) -> u64 {
+        self.cache_creation_input_tokens.saturating_sub(self.cache_creation_1h())
+    }
+
+    /// Add another usage's token counts to this one
+    fn accumulate(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        if other.cache_creation.is_some() {
+            let split = self.cache_creation.get_or_insert_with(CacheCreation::default);
+            split.ephemeral_5m_input_tokens += other.cache_creation_5m();
+            split.ephemeral_1h_input_tokens += other.cache_creation_1h();
+        }
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+    }
+}
+
+impl LogEntry {
+    /// Calendar month (`YYYY-MM`, local time) this entry belongs to
+    /// Entries with a missing or unparseable timestamp map to `UNKNOWN_MONTH`
+    pub fn month_key(&self) -> String {
+        self.timestamp
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|dt| dt.with_timezone(&Local).format("%Y-%m").to_string())
+            .unwrap_or_else(|| UNKNOWN_MONTH.to_string())
+    }
+
+    /// Model that produced this entry, or `UNKNOWN_MODEL` when the log doesn't say
+    pub fn model_key(&self) -> String {
+        self.message
+            .as_ref()
+            .and_then(|m| m.model.clone())
+            .unwrap_or_else(|| UNKNOWN_MODEL.to_string())
+    }
+
+    /// Month and model together ("2025-01 claude-sonnet-4-5"), split with `split_month_model`
+    pub fn month_model_key(&self) -> String {
+        format!("{} {}", self.month_key(), self.model_key())
+    }
+
+    /// Agent this entry belongs to, with the top-level session mapped to `MAIN_AGENT`
+    pub fn agent_key(&self) -> String {
+        self.agent_id.clone().unwrap_or_else(|| MAIN_AGENT.to_string())
+    }
+}
+
+/// Criteria for which log entries count towards aggregated usage
+#[derive(Debug, Default, Clone)]
+pub struct UsageFilter {
+    /// Case-insensitive substring the entry's model name must contain
+    pub model: Option<String>,
+
+    /// Only count entries timestamped at or after this instant
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only read logs under this project directory (see `find_project_dir`)
+    pub project: Option<String>,
+
+    /// Skip project directories whose name matches any of these globs
+    pub exclude: Option<GlobSet>,
+
+    /// Fail on the first malformed line instead of skipping it
+    pub strict: bool,
+
+    /// Only count the newest session, tracked by `UsageTracker` as sessions start
+    pub current_session: bool,
+
+    /// Only count entries logged under this session ID
+    pub session: Option<String>,
+}
+
+impl UsageFilter {
+    /// Create a filter that only matches models containing `model`
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model.map(|m| m.to_lowercase());
+        self
+    }
+
+    /// Create a filter that only matches entries at or after `since`
+    /// Entries without a parseable timestamp never match once this is set
+    pub fn with_since(mut self, since: Option<DateTime<Utc>>) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Create a filter that only reads logs from the project matching `project`
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project = project;
+        self
+    }
+
+    /// Create a filter that skips project directories matching any of `patterns`
+    pub fn with_exclude(mut self, patterns: &[String]) -> Result<Self> {
+        if patterns.is_empty() {
+            self.exclude = None;
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern).with_context(|| format!("Invalid --exclude pattern '{}'", pattern))?);
+        }
+        self.exclude = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Create a filter that fails on malformed lines rather than skipping them
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Create a filter that follows the newest session instead of all time
+    pub fn with_current_session(mut self, current_session: bool) -> Self {
+        self.current_session = current_session;
+        self
+    }
+
+    /// Whether any criteria are set
+    pub fn is_active(&self) -> bool {
+        self.model.is_some() || self.since.is_some() || self.project.is_some() || self.exclude.is_some()
+    }
+
+    /// Whether the project directory `name` is excluded
+    pub fn excludes_project(&self, name: &std::ffi::OsStr) -> bool {
+        self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(name))
+    }
+
+    /// Check whether a log entry passes the filter
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let model_matches = match &self.model {
+            Some(needle) => entry
+                .message
+                .as_ref()
+                .and_then(|m| m.model.as_deref())
+                .is_some_and(|model| model.to_lowercase().contains(needle.as_str())),
+            None => true,
+        };
+
+        let time_matches = match self.since {
+            Some(since) => entry
+                .timestamp
+                .as_deref()
+                .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                .is_some_and(|ts| ts >= since),
+            None => true,
+        };
+
+        let session_matches = match &self.session {
+            Some(session) => entry.session_id.as_ref() == Some(session),
+            None => true,
+        };
+
+        model_matches && time_matches && session_matches
     }
 }
 
+/// Cache efficiency below which the live view suggests prompt caching
+pub const POOR_CACHING_EFFICIENCY: f64 = 1.0;
+
+/// Uncached input tokens needed before the caching hint is worth showing
+pub const POOR_CACHING_MIN_INPUT: u64 = 100_000;
+
+/// Bucket key for entries without a usable timestamp
+pub const UNKNOWN_MONTH: &str = "unknown";
+
+/// Usage bucketed by calendar month (`YYYY-MM` or `UNKNOWN_MONTH`)
+pub type MonthlyUsage = HashMap<String, AggregatedUsage>;
+
+/// Bucket key for log files sitting directly in the projects directory
+pub const UNKNOWN_PROJECT: &str = "unknown";
+
+/// Usage bucketed by project directory name
+pub type ProjectUsage = HashMap<String, AggregatedUsage>;
+
+/// Bucket key for entries without an `agentId` (the top-level session)
+pub const MAIN_AGENT: &str = "main";
+
+/// Usage bucketed by subagent ID (or `MAIN_AGENT`)
+pub type AgentUsage = HashMap<String, AggregatedUsage>;
+
+/// Bucket key for entries that don't name a model
+pub const UNKNOWN_MODEL: &str = "unknown";
+
+/// Usage bucketed by model name (or `UNKNOWN_MODEL`)
+pub type ModelUsage = HashMap<String, AggregatedUsage>;
+
 /// Aggregated usage statistics
 #[derive(Debug, Default, Clone)]
 pub struct AggregatedUsage {
     pub total_input: u64,
     pub total_output: u64,
+    /// All cache writes; `total_cache_creation_1h` of them are one-hour writes
     pub total_cache_creation: u64,
+    pub total_cache_creation_1h: u64,
     pub total_cache_read: u64,
+    pub total_reasoning: u64,
     pub message_count: usize,
+    /// Portion of the totals billed under non-standard service tiers
+    pub by_tier: HashMap<ServiceTier, Usage>,
 }
 
 impl AggregatedUsage {
     pub fn total(&self) -> u64 {
-        self.total_input + self.total_output + self.total_cache_creation + self.total_cache_read
+        self.total_input + self.total_output + self.total_cache_creation + self.total_cache_read + self.total_reasoning
     }
 
     pub fn add(&mut self, usage: &Usage) {
         self.total_input += usage.input_tokens;
         self.total_output += usage.output_tokens;
         self.total_cache_creation += usage.cache_creation_input_tokens;
+        self.total_cache_creation_1h += usage.cache_creation_1h();
         self.total_cache_read += usage.cache_read_input_tokens;
+        self.total_reasoning += usage.reasoning_tokens;
         self.message_count += 1;
+
+        let tier = usage.tier();
+        if tier != ServiceTier::Standard {
+            self.by_tier.entry(tier).or_default().accumulate(usage);
+        }
+    }
+
+    /// Estimate cost in dollars, including cache and reasoning tokens
+    /// One-hour cache writes get their own rate, and tokens billed under batch
+    /// or priority tiers get that tier's multiplier
+    pub fn estimated_cost(&self, pricing: &ModelPricing) -> f64 {
+        let list_cost = crate::pricing::calculate_cost(pricing, self.total_input, self.total_output, 0, self.total_cache_read)
+            + crate::pricing::cache_write_cost(pricing, self.cache_creation_5m(), self.total_cache_creation_1h)
+            + crate::pricing::reasoning_cost(pricing, self.total_reasoning);
+
+        // The totals were priced at standard rates; correct the non-standard share
+        let tier_adjustment: f64 = self
+            .by_tier
+            .iter()
+            .map(|(tier, usage)| {
+                let standard =
+                    crate::pricing::calculate_cost(pricing, usage.input_tokens, usage.output_tokens, 0, usage.cache_read_input_tokens)
+                        + crate::pricing::cache_write_cost(pricing, usage.cache_creation_5m(), usage.cache_creation_1h())
+                        + crate::pricing::reasoning_cost(pricing, usage.reasoning_tokens);
+                standard * (tier.multiplier() - 1.0)
+            })
+            .sum();
+
+        list_cost + tier_adjustment
+    }
+
+    /// Five-minute cache writes: every cache write that isn't a one-hour one
+    pub fn cache_creation_5m(&self) -> u64 {
+        self.total_cache_creation.saturating_sub(self.total_cache_creation_1h)
+    }
+
+    /// Share of prompt tokens served from cache, or None with no usage
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        crate::pricing::cache_hit_ratio(self.total_input, self.total_cache_creation, self.total_cache_read)
+    }
+
+    /// Cache-read tokens per uncached input token, or None with no input
+    pub fn cache_efficiency(&self) -> Option<f64> {
+        if self.total_input == 0 {
+            None
+        } else {
+            Some(self.total_cache_read as f64 / self.total_input as f64)
+        }
+    }
+
+    /// Whether enough uncached input has gone by at a low enough cache efficiency to suggest prompt caching
+    pub fn caching_looks_poor(&self) -> bool {
+        self.total_input >= POOR_CACHING_MIN_INPUT
+            && self.cache_efficiency().is_some_and(|efficiency| efficiency < POOR_CACHING_EFFICIENCY)
+    }
+
+    /// Dollars saved by cache reads versus uncached input
+    pub fn cache_savings(&self, pricing: &ModelPricing) -> f64 {
+        crate::pricing::cache_savings(pricing, self.total_cache_read)
+    }
+
+    /// Fold another aggregate into this one
+    pub fn merge(&mut self, other: &AggregatedUsage) {
+        self.total_input += other.total_input;
+        self.total_output += other.total_output;
+        self.total_cache_creation += other.total_cache_creation;
+        self.total_cache_creation_1h += other.total_cache_creation_1h;
+        self.total_cache_read += other.total_cache_read;
+        self.total_reasoning += other.total_reasoning;
+        self.message_count += other.message_count;
+
+        for (tier, usage) in &other.by_tier {
+            self.by_tier.entry(*tier).or_default().accumulate(usage);
+        }
+    }
+
+    /// Check that a breakdown (per model, project, agent...) adds up to this total
+    /// The error names every count that differs, as `whole` vs `sum of parts`
+    pub fn assert_consistent(&self, parts: &[AggregatedUsage]) -> Result<()> {
+        let sum: AggregatedUsage = parts.iter().sum();
+        let counts = [
+            ("input", self.total_input, sum.total_input),
+            ("output", self.total_output, sum.total_output),
+            ("cache creation", self.total_cache_creation, sum.total_cache_creation),
+            ("1-hour cache creation", self.total_cache_creation_1h, sum.total_cache_creation_1h),
+            ("cache read", self.total_cache_read, sum.total_cache_read),
+            ("reasoning", self.total_reasoning, sum.total_reasoning),
+            ("messages", self.message_count as u64, sum.message_count as u64),
+        ];
+
+        let mismatches: Vec<String> = counts
+            .iter()
+            .filter(|(_, whole, parts)| whole != parts)
+            .map(|(name, whole, parts)| format!("{} {} vs {}", name, whole, parts))
+            .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Breakdown doesn't add up to the total: {}", mismatches.join(", "))
+        }
+    }
+}
+
+impl<'a> std::iter::Sum<&'a AggregatedUsage> for AggregatedUsage {
+    fn sum<I: Iterator<Item = &'a AggregatedUsage>>(iter: I) -> Self {
+        iter.fold(AggregatedUsage::default(), |mut total, usage| {
+            total.merge(usage);
+            total
+        })
+    }
+}
+
+/// Smoothed token and cost burn rate across live refreshes
+#[derive(Debug, Default)]
+pub struct BurnRate {
+    /// Time, total tokens, and cost at the previous sample
+    previous: Option<(Instant, u64, f64)>,
+    tokens_per_minute: Option<f64>,
+    cost_per_hour: Option<f64>,
+}
+
+impl BurnRate {
+    /// Weight given to the newest sample in the exponential moving average
+    const SMOOTHING: f64 = 0.3;
+
+    /// Record a new sample of the running totals
+    /// Decreasing totals (e.g. rotated logs) count as zero usage rather than negative
+    pub fn update(&mut self, usage: &AggregatedUsage, cost: f64) {
+        let now = Instant::now();
+        let total = usage.total();
+
+        if let Some((at, prev_total, prev_cost)) = self.previous {
+            let minutes = now.duration_since(at).as_secs_f64() / 60.0;
+            if minutes > 0.0 {
+                let tokens_per_minute = total.saturating_sub(prev_total) as f64 / minutes;
+                let cost_per_hour = (cost - prev_cost).max(0.0) / minutes * 60.0;
+
+                self.tokens_per_minute = Some(Self::smooth(self.tokens_per_minute, tokens_per_minute));
+                self.cost_per_hour = Some(Self::smooth(self.cost_per_hour, cost_per_hour));
+            }
+        }
+
+        self.previous = Some((now, total, cost));
+    }
+
+    fn smooth(previous: Option<f64>, sample: f64) -> f64 {
+        match previous {
+            Some(prev) => prev + Self::SMOOTHING * (sample - prev),
+            None => sample,
+        }
     }
 }
 
+impl fmt::Display for BurnRate {
+    /// "N tokens/min · $X/hour" (in the display currency), or "—" before the second sample
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.tokens_per_minute, self.cost_per_hour) {
+            (Some(tokens), Some(cost)) => write!(
+                f,
+                "{} tokens/min · {}/hour",
+                format_number(tokens.round() as u64),
+                crate::display::format_cost(cost)
+            ),
+            _ => write!(f, "—"),
+        }
+    }
+}
+
+/// Environment variable that overrides the default projects directory
+pub const PROJECTS_DIR_ENV: &str = "CLAUDE_PROJECTS_DIR";
+
 /// Find Claude Code projects directory
-pub fn get_claude_projects_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
+///
+/// Resolution order: explicit `override_dir` (e.g. `--projects-dir`), then the
+/// `CLAUDE_PROJECTS_DIR` environment variable, then `~/.claude/projects`.
+pub fn get_claude_projects_dir(override_dir: Option<&Path>) -> Result<PathBuf> {
+    let env_dir = std::env::var_os(PROJECTS_DIR_ENV).filter(|v| !v.is_empty());
+
+    if let Some(dir) = override_dir.map(Path::to_path_buf).or_else(|| env_dir.map(PathBuf::from)) {
+        if !dir.is_dir() {
+            anyhow::bail!(
+                "Claude Code projects directory override not found at {:?}\n\
+                 Check the --projects-dir flag or the {} environment variable.",
+                dir,
+                PROJECTS_DIR_ENV
+            );
+        }
+        return Ok(dir);
+    }
+
+    let home = dirs::home_dir().with_context(|| {
+        format!(
+            "Could not find home directory. Pass --projects-dir or set {} to locate Claude Code logs.",
+            PROJECTS_DIR_ENV
+        )
+    })?;
     let claude_dir = home.join(".claude").join("projects");
 
     if !claude_dir.exists() {
         anyhow::bail!(
             "Claude Code projects directory not found at {:?}\n\
-             Make sure you have used Claude Code at least once.",
-            claude_dir
+             Make sure you have used Claude Code at least once, or point to a\n\
+             different location with --projects-dir or {}.",
+            claude_dir,
+            PROJECTS_DIR_ENV
         );
     }
 
     Ok(claude_dir)
 }
 
-/// Find all JSONL files in Claude Code projects
-pub fn find_jsonl_files() -> Result<Vec<PathBuf>> {
-    let projects_dir = get_claude_projects_dir()?;
+/// Check whether a path is a plain (`.jsonl`) or gzip-compressed (`.jsonl.gz`) log
+fn is_jsonl_log(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|s| s.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    name.ends_with(".jsonl") || name.ends_with(".jsonl.gz")
+}
+
+/// Find the project directory under `projects_dir` matching `project`
+///
+/// Claude Code names each project directory after its working directory with
+/// `/` and `.` replaced by `-` (e.g. `-home-me-my-repo`), so this accepts the
+/// exact directory name, the original path, or just the trailing repo name.
+pub fn find_project_dir(projects_dir: &Path, project: &str) -> Result<PathBuf> {
+    let encoded = project.replace(['/', '.'], "-");
+
+    let mut names: Vec<String> = std::fs::read_dir(projects_dir)
+        .with_context(|| format!("Could not read projects directory {:?}", projects_dir))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    if let Some(exact) = names.iter().find(|name| **name == project || **name == encoded) {
+        return Ok(projects_dir.join(exact));
+    }
+
+    let matches: Vec<&String> = names.iter().filter(|name| project_matches(name, project)).collect();
+    match matches.as_slice() {
+        [only] => Ok(projects_dir.join(only)),
+        [] => anyhow::bail!(
+            "No project matching '{}' in {:?}\nAvailable projects:\n  {}",
+            project,
+            projects_dir,
+            names.join("\n  ")
+        ),
+        several => anyhow::bail!(
+            "Project name '{}' is ambiguous; it matches:\n  {}\nUse the full directory name instead.",
+            project,
+            several.iter().map(|s| s.as_str()).collect::<Vec<_>>().join("\n  ")
+        ),
+    }
+}
+
+/// Whether `project` (a directory name, original path, or trailing repo name) names the
+/// project directory `dir_name`, by the same rules as `find_project_dir`
+pub fn project_matches(dir_name: &str, project: &str) -> bool {
+    let encoded = project.replace(['/', '.'], "-");
+    dir_name == project || dir_name == encoded || dir_name.ends_with(&format!("-{}", encoded.trim_start_matches('-')))
+}
+
+/// Set by `--raw-names` to show project directory names exactly as Claude Code encodes them
+static RAW_PROJECT_NAMES: AtomicBool = AtomicBool::new(false);
+
+/// Show encoded project directory names instead of decoded paths for the rest of the process
+pub fn set_raw_project_names(raw: bool) {
+    RAW_PROJECT_NAMES.store(raw, Ordering::Relaxed);
+}
+
+/// Human-readable name for a project directory, e.g. `/home/me/my-repo` for `-home-me-my-repo`
+///
+/// The encoding loses whether each `-` was a `/`, a `.`, or a dash, so the
+/// path is followed on disk. When no existing path (or more than one) fits,
+/// the best guess is shown next to the encoded name.
+pub fn readable_project_name(name: &str) -> String {
+    if RAW_PROJECT_NAMES.load(Ordering::Relaxed) || !name.starts_with('-') {
+        return name.to_string();
+    }
+
+    match decode_project_dir(Path::new("/"), name).as_slice() {
+        [only] => only.display().to_string(),
+        [first, ..] => format!("{} ({})", first.display(), name),
+        [] => format!("/{} ({})", name.trim_start_matches('-').replace('-', "/"), name),
+    }
+}
+
+/// Existing directories under `root` whose encoded form is `name`, stopping at two
+fn decode_project_dir(root: &Path, name: &str) -> Vec<PathBuf> {
+    let parts: Vec<&str> = name.trim_start_matches('-').split('-').collect();
+    let mut found = Vec::new();
+    if let Some((first, rest)) = parts.split_first() {
+        follow_encoded_path(root, first.to_string(), rest, &mut found);
+    }
+    found
+}
+
+/// Extend `component` (the name being built inside `dir`) with each remaining part,
+/// trying each character the `-` before it could have been
+fn follow_encoded_path(dir: &Path, component: String, rest: &[&str], found: &mut Vec<PathBuf>) {
+    if found.len() >= 2 {
+        return;
+    }
+
+    let Some((next, rest)) = rest.split_first() else {
+        let path = dir.join(&component);
+        if !component.is_empty() && path.is_dir() {
+            found.push(path);
+        }
+        return;
+    };
+
+    // Only keep going while some entry in `dir` could still be this component
+    let entries: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    if !entries.iter().any(|entry| entry.starts_with(&component)) {
+        return;
+    }
+
+    if !component.is_empty() && entries.contains(&component) && dir.join(&component).is_dir() {
+        follow_encoded_path(&dir.join(&component), next.to_string(), rest, found);
+    }
+    for sep in ['-', '.'] {
+        follow_encoded_path(dir, format!("{}{}{}", component, sep, next), rest, found);
+    }
+}
+
+/// Paths already warned about while walking, so each refresh doesn't repeat them
+static WARNED_PATHS: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+
+/// Print `message` as a warning the first time `path` comes up in this process
+fn warn_once_for(path: &Path, message: impl fmt::Display) {
+    let mut warned = WARNED_PATHS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    if warned.insert(path.to_path_buf()) {
+        tracing::warn!("{}", message);
+    }
+}
+
+/// Find all JSONL files (plain and gzip-compressed) in Claude Code projects
+/// When `filter` names a project, only that project's directory is searched;
+/// project directories it excludes are skipped without being walked.
+/// Symlinks are followed, but a directory reached twice (through a cycle or a
+/// second link) is only walked once, and broken links are skipped with a warning.
+pub fn find_jsonl_files(projects_dir: &Path, filter: &UsageFilter) -> Result<Vec<PathBuf>> {
+    let _span = tracing::debug_span!("find_jsonl_files", dir = %projects_dir.display()).entered();
+    let root = match &filter.project {
+        Some(project) => find_project_dir(projects_dir, project)?,
+        None => projects_dir.to_path_buf(),
+    };
+
+    let is_excluded = |entry: &walkdir::DirEntry| {
+        let excluded = entry.file_type().is_dir()
+            && entry.path().parent() == Some(projects_dir)
+            && filter.excludes_project(entry.file_name());
+        if excluded {
+            tracing::debug!(project = ?entry.file_name(), "excluded by --exclude");
+        }
+        excluded
+    };
+
+    // Canonical paths of directories already walked
+    let mut visited = HashSet::new();
+    let mut first_visit = |entry: &walkdir::DirEntry| {
+        !entry.file_type().is_dir()
+            || visited.insert(std::fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf()))
+    };
 
     let mut files = Vec::new();
-    for entry in WalkDir::new(projects_dir)
+    for entry in WalkDir::new(root)
         .follow_links(true)
         .into_iter()
-        .filter_map(|e| e.ok())
+        .filter_entry(|e| !is_excluded(e) && first_visit(e))
     {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                if let (Some(path), Some(ancestor)) = (e.path(), e.loop_ancestor()) {
+                    warn_once_for(path, format_args!("Skipping symlink loop {:?} (points back to {:?})", path, ancestor));
+                } else if let Some(path) = e.path().filter(|path| path.is_symlink() && !path.exists()) {
+                    warn_once_for(path, format_args!("Skipping broken symlink {:?}", path));
+                } else {
+                    tracing::debug!(error = %e, "skipping unreadable entry");
+                }
+                continue;
+            }
+        };
+
         let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+        if is_jsonl_log(path) {
+            tracing::trace!(path = %path.display(), "found log file");
             files.push(path.to_path_buf());
         }
     }
 
+    tracing::debug!(files = files.len(), "walk finished");
     Ok(files)
 }
 
-/// Parse a single JSONL file and aggregate usage
-pub fn parse_jsonl_file(path: &PathBuf) -> Result<AggregatedUsage> {
+/// Names of the project directories under `projects_dir` that `filter` excludes
+pub fn excluded_projects(projects_dir: &Path, filter: &UsageFilter) -> Result<Vec<String>> {
+    let mut excluded = Vec::new();
+    if filter.exclude.is_none() {
+        return Ok(excluded);
+    }
+
+    for entry in std::fs::read_dir(projects_dir)
+        .with_context(|| format!("Failed to list projects in {:?}", projects_dir))?
+        .filter_map(|e| e.ok())
+    {
+        if entry.path().is_dir() && filter.excludes_project(&entry.file_name()) {
+            excluded.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+
+    excluded.sort();
+    Ok(excluded)
+}
+
+/// Open a log file for reading, transparently decompressing `.gz` files
+pub fn open_log_reader(path: &Path) -> Result<Box<dyn BufRead>> {
     let file = File::open(path)
         .with_context(|| format!("Failed to open {:?}", path))?;
-    let reader = BufReader::new(file);
 
-    let mut aggregated = AggregatedUsage::default();
+    if path.extension().and_then(|s| s.to_str()) == Some("gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Parse one log line, or `None` for a well-formed line that carries no usage
+///
+/// Most lines (user turns, tool results, summaries) have no usage at all, so
+/// they are only checked to be complete JSON rather than deserialized in full;
+/// a corrupt one is still an error, just as the full parse would report it
+pub fn parse_log_line(line: &str) -> serde_json::Result<Option<LogEntry>> {
+    if line.contains("\"usage\"") {
+        serde_json::from_str::<LogEntry>(line).map(Some)
+    } else {
+        serde_json::from_str::<serde::de::IgnoredAny>(line).map(|_| None)
+    }
+}
+
+/// Walk JSONL log lines from a reader, calling `visit` for every entry with usage
+/// that passes `filter`. `path` and `first_line` (lines already consumed before
+/// this reader) are only used to label warnings for malformed lines
+fn visit_usage<R, F>(mut reader: R, path: &Path, first_line: usize, filter: &UsageFilter, mut visit: F) -> Result<usize>
+where
+    R: BufRead,
+    F: FnMut(&LogEntry, &Usage),
+{
+    // Reuse one buffer for every line rather than allocating a String per line
+    let mut line = String::new();
+    let mut line_num = first_line;
 
-    for (line_num, line) in reader.lines().enumerate() {
-        let line = line.context("Failed to read line")?;
+    // Malformed lines are summarized once per file rather than warned about one by one
+    let mut malformed = 0;
+    let mut first_error = None;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).context("Failed to read line")? == 0 {
+            break;
+        }
+        line_num += 1;
 
         // Skip empty lines
         if line.trim().is_empty() {
             continue;
         }
 
-        // Parse JSON
-        match serde_json::from_str::<LogEntry>(&line) {
-            Ok(entry) => {
-                if let Some(message) = entry.message {
-                    if let Some(usage) = message.usage {
-                        aggregated.add(&usage);
-                    }
+        match parse_log_line(&line) {
+            Ok(None) => {}
+            Ok(Some(entry)) => {
+                if !filter.matches(&entry) {
+                    continue;
                 }
+                if let Some(usage) = entry.message.as_ref().and_then(|m| m.usage.as_ref()) {
+                    visit(&entry, usage);
+                }
+            }
+            Err(e) if filter.strict => {
+                anyhow::bail!("Malformed line {} in {:?}: {}", line_num, path, e);
             }
             Err(e) => {
-                // Skip malformed lines but warn
-                eprintln!("Warning: Failed to parse line {} in {:?}: {}", line_num + 1, path, e);
+                tracing::debug!(line = line_num, error = %e, "malformed line");
+                malformed += 1;
+                first_error.get_or_insert((line_num, e));
             }
         }
     }
 
+    if let Some((first_line_num, e)) = first_error {
+        tracing::warn!(
+            "Skipped {} malformed line(s) in {:?} (first at line {}: {})",
+            malformed, path, first_line_num, e
+        );
+    }
+
+    Ok(line_num - first_line)
+}
+
+/// Parse JSONL log lines from a reader and aggregate usage
+/// `path` is only used to label warnings for malformed lines
+pub fn parse_jsonl_file(reader: impl BufRead, path: &Path, filter: &UsageFilter) -> Result<AggregatedUsage> {
+    let _span = tracing::debug_span!("parse_jsonl_file", path = %path.display()).entered();
+    let mut aggregated = AggregatedUsage::default();
+    let lines = visit_usage(reader, path, 0, filter, |_, usage| aggregated.add(usage))?;
+    tracing::debug!(lines, messages = aggregated.message_count, "parsed");
     Ok(aggregated)
 }
 
-/// Parse all JSONL files and return aggregated usage
-pub fn parse_all_files() -> Result<AggregatedUsage> {
-    let files = find_jsonl_files()?;
+/// Parse JSONL log lines from a reader and aggregate usage under `key(entry)`
+fn bucket_jsonl_file(
+    reader: impl BufRead,
+    path: &Path,
+    filter: &UsageFilter,
+    key: fn(&LogEntry) -> String,
+) -> Result<HashMap<String, AggregatedUsage>> {
+    let mut buckets = HashMap::new();
+    visit_usage(reader, path, 0, filter, |entry, usage| {
+        buckets.entry(key(entry)).or_insert_with(AggregatedUsage::default).add(usage);
+    })?;
+    Ok(buckets)
+}
+
+/// Parse JSONL log lines from a reader and aggregate usage per calendar month
+pub fn parse_jsonl_file_by_month(reader: impl BufRead, path: &Path, filter: &UsageFilter) -> Result<MonthlyUsage> {
+    bucket_jsonl_file(reader, path, filter, LogEntry::month_key)
+}
+
+/// Parse JSONL log lines from a reader and aggregate usage per agent
+pub fn parse_jsonl_file_by_agent(reader: impl BufRead, path: &Path, filter: &UsageFilter) -> Result<AgentUsage> {
+    bucket_jsonl_file(reader, path, filter, LogEntry::agent_key)
+}
+
+/// Parse JSONL log lines from a reader and aggregate usage per model
+pub fn parse_jsonl_file_by_model(reader: impl BufRead, path: &Path, filter: &UsageFilter) -> Result<ModelUsage> {
+    bucket_jsonl_file(reader, path, filter, LogEntry::model_key)
+}
+
+/// Error for an empty walk, noting when `--exclude` may be the reason
+fn no_files_message(filter: &UsageFilter) -> &'static str {
+    if filter.exclude.is_some() {
+        "No JSONL files found in Claude Code projects directory (after applying --exclude)"
+    } else {
+        "No JSONL files found in Claude Code projects directory"
+    }
+}
+
+/// Set by `--concurrency`; when unset, rayon's default (`RAYON_NUM_THREADS`, else one thread per CPU)
+static PARSE_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+
+/// Thread pool log files are parsed on, built on first use
+static PARSE_POOL: OnceLock<ThreadPool> = OnceLock::new();
+
+/// Cap the number of threads parsing log files for the rest of the process
+/// Only the first call before any parsing takes effect; values below 1 are treated as 1
+pub fn set_parse_concurrency(threads: usize) {
+    let _ = PARSE_CONCURRENCY.set(threads.max(1));
+}
+
+/// The pool shared by one-shot parsing and `UsageTracker::refresh`
+fn parse_pool() -> &'static ThreadPool {
+    PARSE_POOL.get_or_init(|| {
+        // Zero lets rayon pick, which honors RAYON_NUM_THREADS
+        let threads = PARSE_CONCURRENCY.get().copied().unwrap_or(0);
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("log-parser-{}", index))
+            .build()
+            .expect("failed to start log parsing threads")
+    })
+}
+
+/// Run `parse` over every file on `pool`, returning results in file order
+fn parse_in_parallel<T, F>(pool: &ThreadPool, files: &[PathBuf], parse: F) -> Vec<Result<T>>
+where
+    T: Send,
+    F: Fn(&Path) -> Result<T> + Sync,
+{
+    pool.install(|| files.par_iter().map(|file| parse(file)).collect())
+}
+
+/// Open and parse every JSONL file under the projects directory with `parse`,
+/// spreading files over the parsing thread pool
+/// Files that fail to open or parse are skipped with a warning, unless `filter.strict` is set
+fn parse_each_file<T, F>(projects_dir: &Path, filter: &UsageFilter, parse: F) -> Result<Vec<(PathBuf, T)>>
+where
+    T: Send,
+    F: Fn(&Path, Box<dyn BufRead>) -> Result<T> + Sync,
+{
+    let files = find_jsonl_files(projects_dir, filter)?;
 
     if files.is_empty() {
-        anyhow::bail!("No JSONL files found in Claude Code projects directory");
+        anyhow::bail!(no_files_message(filter));
     }
 
+    let pool = parse_pool();
+    tracing::debug!(files = files.len(), threads = pool.current_num_threads(), "parsing log files");
+    let results = parse_in_parallel(pool, &files, |file| open_log_reader(file).and_then(|reader| parse(file, reader)));
+
+    let mut parsed = Vec::with_capacity(files.len());
+    for (file, result) in files.into_iter().zip(results) {
+        match result {
+            Err(e) if filter.strict => return Err(e),
+            Err(e) => tracing::warn!("Failed to parse {:?}: {}", file, e),
+            Ok(value) => parsed.push((file, value)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Parse all JSONL files under the projects directory and return aggregated usage
+/// Only entries passing `filter` are counted
+pub fn parse_all_files(projects_dir: &Path, filter: &UsageFilter) -> Result<AggregatedUsage> {
     let mut total = AggregatedUsage::default();
 
-    for file in files {
-        match parse_jsonl_file(&file) {
-            Ok(usage) => {
-                total.total_input += usage.total_input;
-                total.total_output += usage.total_output;
-                total.total_cache_creation += usage.total_cache_creation;
-                total.total_cache_read += usage.total_cache_read;
-                total.message_count += usage.message_count;
+    for (_, usage) in parse_each_file(projects_dir, filter, |file, reader| parse_jsonl_file(reader, file, filter))? {
+        total.merge(&usage);
+    }
+
+    Ok(total)
+}
+
+/// Parse all JSONL files under the projects directory and bucket usage under `key(entry)`
+fn bucket_all_files(
+    projects_dir: &Path,
+    filter: &UsageFilter,
+    key: fn(&LogEntry) -> String,
+) -> Result<HashMap<String, AggregatedUsage>> {
+    let mut buckets: HashMap<String, AggregatedUsage> = HashMap::new();
+
+    let parsed = parse_each_file(projects_dir, filter, |file, reader| bucket_jsonl_file(reader, file, filter, key))?;
+    for (bucket, usage) in parsed.into_iter().flat_map(|(_, file_buckets)| file_buckets) {
+        buckets.entry(bucket).or_default().merge(&usage);
+    }
+
+    Ok(buckets)
+}
+
+/// Parse all JSONL files under the projects directory and bucket usage by month
+pub fn parse_all_files_by_month(projects_dir: &Path, filter: &UsageFilter) -> Result<MonthlyUsage> {
+    bucket_all_files(projects_dir, filter, LogEntry::month_key)
+}
+
+/// Parse all JSONL files under the projects directory and bucket usage by agent
+pub fn parse_all_files_by_agent(projects_dir: &Path, filter: &UsageFilter) -> Result<AgentUsage> {
+    bucket_all_files(projects_dir, filter, LogEntry::agent_key)
+}
+
+/// Parse all JSONL files under the projects directory and bucket usage by model
+pub fn parse_all_files_by_model(projects_dir: &Path, filter: &UsageFilter) -> Result<ModelUsage> {
+    bucket_all_files(projects_dir, filter, LogEntry::model_key)
+}
+
+/// Name of the project a log file belongs to: its top-level directory under `projects_dir`
+pub fn project_name(projects_dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(projects_dir).unwrap_or(file);
+    let mut components = relative.components();
+
+    match (components.next(), components.next()) {
+        (Some(project), Some(_)) => project.as_os_str().to_string_lossy().into_owned(),
+        _ => UNKNOWN_PROJECT.to_string(),
+    }
+}
+
+/// Parse all JSONL files under the projects directory and bucket usage by project
+pub fn parse_all_files_by_project(projects_dir: &Path, filter: &UsageFilter) -> Result<ProjectUsage> {
+    let mut projects = ProjectUsage::new();
+
+    for (file, usage) in parse_each_file(projects_dir, filter, |file, reader| parse_jsonl_file(reader, file, filter))? {
+        projects.entry(project_name(projects_dir, &file)).or_default().merge(&usage);
+    }
+
+    Ok(projects)
+}
+
+/// Split a `LogEntry::month_model_key` back into its month and model
+pub fn split_month_model(key: &str) -> (&str, &str) {
+    key.split_once(' ').unwrap_or((key, UNKNOWN_MODEL))
+}
+
+/// How much of the end of a log file `latest_session` reads
+const SESSION_TAIL_BYTES: u64 = 256 * 1024;
+
+/// Session ID of the most recent entry among `files`, or `None` if no entry carries one
+/// Logs are appended in time order, so only the tail of the most recently modified
+/// uncompressed file is read rather than every entry.
+pub fn latest_session(files: &[PathBuf]) -> Result<Option<String>> {
+    let newest = files
+        .iter()
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .filter_map(|path| Some((std::fs::metadata(path).ok()?.modified().ok()?, path)))
+        .max_by_key(|(modified, _)| *modified);
+    let Some((_, path)) = newest else {
+        return Ok(None);
+    };
+
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(SESSION_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+
+    let tail = String::from_utf8_lossy(&tail);
+    let mut lines: Vec<&str> = tail.lines().collect();
+    // The first line is cut off unless the tail starts at the beginning of the file
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+
+    Ok(lines
+        .iter()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<LogEntry>(line).ok())
+        .find_map(|entry| entry.session_id))
+}
+
+/// Key used by a `UsageTracker` that only needs overall totals
+fn no_key(_: &LogEntry) -> String {
+    String::new()
+}
+
+/// Read position and per-bucket usage for one log file tracked across refreshes
+#[derive(Debug, Default)]
+struct TrackedFile {
+    /// Bytes consumed so far (always at a line boundary)
+    offset: u64,
+    /// Lines consumed so far, to keep warning line numbers accurate
+    lines: usize,
+    modified: Option<SystemTime>,
+    buckets: HashMap<String, AggregatedUsage>,
+}
+
+/// Incrementally aggregates usage across live refreshes
+///
+/// Each `refresh` re-walks the projects directory to pick up new files, but
+/// only reads the bytes appended to each file since the previous refresh.
+/// Files that shrink or are rewritten in place are re-read from the start, and
+/// gzip archives (which can't be resumed mid-stream) are re-read whenever they change.
+#[derive(Debug)]
+pub struct UsageTracker {
+    projects_dir: PathBuf,
+    filter: UsageFilter,
+    key: fn(&LogEntry) -> String,
+    files: HashMap<PathBuf, TrackedFile>,
+}
+
+impl UsageTracker {
+    /// Track overall usage under `projects_dir` for entries passing `filter`
+    pub fn new(projects_dir: &Path, filter: &UsageFilter) -> Self {
+        Self {
+            projects_dir: projects_dir.to_path_buf(),
+            filter: filter.clone(),
+            key: no_key,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Also bucket usage under `key(entry)` (e.g. `LogEntry::month_key`)
+    pub fn with_key(mut self, key: fn(&LogEntry) -> String) -> Self {
+        self.key = key;
+        self.files.clear();
+        self
+    }
+
+    /// Pick up new files and appended lines since the last refresh
+    /// Files that fail to read are skipped with a warning (or fail the refresh when strict), as in `parse_all_files`
+    pub fn refresh(&mut self) -> Result<()> {
+        let files = find_jsonl_files(&self.projects_dir, &self.filter)?;
+
+        if files.is_empty() {
+            anyhow::bail!(no_files_message(&self.filter));
+        }
+
+        // A new session started: count it from scratch
+        if self.filter.current_session {
+            if let Some(latest) = latest_session(&files)? {
+                if self.filter.session.as_ref() != Some(&latest) {
+                    self.filter.session = Some(latest);
+                    self.files.clear();
+                }
             }
-            Err(e) => {
-                eprintln!("Warning: Failed to parse {:?}: {}", file, e);
+        }
+
+        // Forget files that were deleted or moved away
+        self.files.retain(|path, _| files.contains(path));
+
+        // Files are brought up to date on the parsing pool, then errors reported in file order
+        let mut states: Vec<(PathBuf, TrackedFile)> = files
+            .into_iter()
+            .map(|file| {
+                let state = self.files.remove(&file).unwrap_or_default();
+                (file, state)
+            })
+            .collect();
+        let this = &*self;
+        let results: Vec<Result<()>> = parse_pool()
+            .install(|| states.par_iter_mut().map(|(file, state)| this.update_file(file, state)).collect());
+
+        let mut first_error = None;
+        for ((file, state), result) in states.into_iter().zip(results) {
+            match result {
+                Err(e) if self.filter.strict => {
+                    first_error.get_or_insert(e);
+                }
+                Err(e) => tracing::warn!("Failed to parse {:?}: {}", file, e),
+                Ok(()) => {}
             }
+            self.files.insert(file, state);
         }
+
+        first_error.map_or(Ok(()), Err)
     }
 
-    Ok(total)
+    /// Bring one file's state up to date, reading only what changed
+    fn update_file(&self, path: &Path, state: &mut TrackedFile) -> Result<()> {
+        let metadata = std::fs::metadata(path).with_context(|| format!("Failed to stat {:?}", path))?;
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+        let compressed = path.extension().and_then(|s| s.to_str()) == Some("gz");
+
+        let unchanged = len == state.offset && modified == state.modified;
+        if unchanged {
+            return Ok(());
+        }
+
+        // Truncated, rewritten in place, or compressed: start this file over
+        if len <= state.offset || compressed {
+            *state = TrackedFile::default();
+        }
+        state.modified = modified;
+
+        let key = self.key;
+        let buckets = &mut state.buckets;
+        let mut record = |entry: &LogEntry, usage: &Usage| {
+            buckets.entry(key(entry)).or_default().add(usage);
+        };
+
+        if compressed {
+            state.lines = visit_usage(open_log_reader(path)?, path, 0, &self.filter, &mut record)?;
+            state.offset = len;
+            return Ok(());
+        }
+
+        let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+        file.seek(SeekFrom::Start(state.offset))?;
+        let mut appended = Vec::new();
+        file.take(len - state.offset).read_to_end(&mut appended)?;
+
+        // Leave a partially written last line for the next refresh
+        let complete = match appended.iter().rposition(|&b| b == b'\n') {
+            Some(last_newline) => last_newline + 1,
+            None => return Ok(()),
+        };
+
+        let lines = visit_usage(&appended[..complete], path, state.lines, &self.filter, &mut record)?;
+        state.lines += lines;
+        state.offset += complete as u64;
+
+        Ok(())
+    }
+
+    /// Session being counted with `UsageFilter::current_session`, once one has been seen in the logs
+    pub fn session(&self) -> Option<&str> {
+        self.filter.session.as_deref()
+    }
+
+    /// Usage summed across every tracked file
+    pub fn total(&self) -> AggregatedUsage {
+        self.files.values().flat_map(|f| f.buckets.values()).sum()
+    }
+
+    /// Usage bucketed by this tracker's key
+    pub fn buckets(&self) -> HashMap<String, AggregatedUsage> {
+        let mut buckets: HashMap<String, AggregatedUsage> = HashMap::new();
+        for (bucket, usage) in self.files.values().flat_map(|f| &f.buckets) {
+            buckets.entry(bucket.clone()).or_default().merge(usage);
+        }
+        buckets
+    }
+
+    /// Usage bucketed by project directory name and this tracker's key
+    pub fn by_project_and_key(&self) -> HashMap<(String, String), AggregatedUsage> {
+        let mut buckets: HashMap<(String, String), AggregatedUsage> = HashMap::new();
+        for (path, state) in &self.files {
+            let project = project_name(&self.projects_dir, path);
+            for (bucket, usage) in &state.buckets {
+                buckets.entry((project.clone(), bucket.clone())).or_default().merge(usage);
+            }
+        }
+        buckets
+    }
+
+    /// Usage bucketed by project directory name
+    pub fn by_project(&self) -> ProjectUsage {
+        let mut projects = ProjectUsage::new();
+        for (path, state) in &self.files {
+            let project = projects.entry(project_name(&self.projects_dir, path)).or_default();
+            for usage in state.buckets.values() {
+                project.merge(usage);
+            }
+        }
+        projects
+    }
+}
+
+/// How often a watching live view checks for log changes; writes landing
+/// within one interval are coalesced into a single redraw
+pub const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Filesystem watch on the projects directory that flags changes to JSONL logs
+/// Reads (access events) are ignored, so parsing the logs doesn't retrigger it
+pub struct LogWatcher {
+    _watcher: notify::RecommendedWatcher,
+    changes: std::sync::mpsc::Receiver<()>,
+}
+
+impl LogWatcher {
+    /// Watch `projects_dir` recursively
+    /// Fails when the platform has no watch API or its watch limit is reached
+    pub fn new(projects_dir: &Path) -> Result<Self> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let (tx, changes) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                let relevant = !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|p| is_jsonl_log(p));
+                if relevant {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .context("Failed to create a file watcher")?;
+        watcher
+            .watch(projects_dir, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {:?}", projects_dir))?;
+
+        Ok(Self { _watcher: watcher, changes })
+    }
+
+    /// Whether any log changed since the last call, without blocking
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+        while self.changes.try_recv().is_ok() {
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A representative mix of lines: only the assistant turns carry usage
+    const MIXED_LOG: &str = r#"{"type":"user","message":{"role":"user","content":"hello"},"timestamp":"2025-12-02T10:00:00Z"}
+{"type":"assistant","message":{"model":"claude-sonnet-4-5","usage":{"input_tokens":100,"output_tokens":20,"cache_creation_input_tokens":300,"cache_read_input_tokens":4000}},"timestamp":"2025-12-02T10:00:05Z"}
+{"type":"user","message":{"role":"user","content":[{"type":"tool_result","content":"the word usage appears here"}]}}
+{"type":"summary","summary":"Refactor session","leafUuid":"abc"}
+
+{"type":"assistant","message":{"model":"claude-opus-4-1","usage":{"input_tokens":7,"output_tokens":3}},"timestamp":"2025-11-30T23:59:59Z"}
+"#;
+
+    fn reader(contents: &str) -> Cursor<&[u8]> {
+        Cursor::new(contents.as_bytes())
+    }
+
+    /// Parse a file from `tests/fixtures/local` through the same path real logs take
+    fn parse_fixture(name: &str) -> AggregatedUsage {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("local")
+            .join(name);
+        let reader = open_log_reader(&path).unwrap();
+        parse_jsonl_file(reader, &path, &UsageFilter::default()).unwrap()
+    }
+
+    #[test]
+    fn sums_all_four_token_kinds_across_lines() {
+        let usage = parse_fixture("valid.jsonl");
+
+        assert_eq!(usage.message_count, 3);
+        assert_eq!(usage.total_input, 1_234 + 10 + 5);
+        assert_eq!(usage.total_output, 567 + 20 + 5);
+        assert_eq!(usage.total_cache_creation, 890 + 30 + 5);
+        assert_eq!(usage.total_cache_read, 123 + 40 + 5);
+    }
+
+    #[test]
+    fn flags_poor_caching_only_across_meaningful_volume() {
+        let mut usage = AggregatedUsage::default();
+        assert_eq!(usage.cache_efficiency(), None);
+        assert!(!usage.caching_looks_poor());
+
+        usage.total_input = 1_000;
+        usage.total_cache_read = 500;
+        assert_eq!(usage.cache_efficiency(), Some(0.5));
+        assert!(!usage.caching_looks_poor());
+
+        usage.total_input = 200_000;
+        usage.total_cache_read = 100_000;
+        assert!(usage.caching_looks_poor());
+
+        usage.total_cache_read = 2_000_000;
+        assert!(!usage.caching_looks_poor());
+    }
+
+    #[test]
+    fn gzip_fixture_matches_plain_fixture() {
+        let plain = parse_fixture("valid.jsonl");
+        let compressed = parse_fixture("valid.jsonl.gz");
+
+        assert_eq!(compressed.message_count, plain.message_count);
+        assert_eq!(compressed.total(), plain.total());
+    }
+
+    #[test]
+    fn skips_blank_lines() {
+        let usage = parse_fixture("blank_lines.jsonl");
+
+        assert_eq!(usage.message_count, 2);
+        assert_eq!(usage.total(), 300);
+    }
+
+    #[test]
+    fn skips_malformed_lines_and_keeps_valid_ones() {
+        let usage = parse_fixture("malformed.jsonl");
+
+        assert_eq!(usage.message_count, 2);
+        assert_eq!(usage.total_input, 150);
+    }
+
+    #[test]
+    fn strict_filter_fails_on_the_first_malformed_line() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/local/malformed.jsonl");
+        let reader = open_log_reader(&path).unwrap();
+        let err = parse_jsonl_file(reader, &path, &UsageFilter::default().with_strict(true)).unwrap_err();
+
+        assert!(err.to_string().starts_with("Malformed line 2 in"));
+    }
+
+    #[test]
+    fn strict_filter_fails_on_a_truncated_line_without_usage() {
+        let path = Path::new("session.jsonl");
+        let complete = r#"{"message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#;
+        let log = format!("{}\n{{\"type\":\"user\",\"message\":{{\"content\":\"hi\n{}\n", complete, complete);
+
+        let err = parse_jsonl_file(log.as_bytes(), path, &UsageFilter::default().with_strict(true)).unwrap_err();
+        assert!(err.to_string().starts_with("Malformed line 2 in"));
+
+        // Without --strict the line is skipped and the usage around it still counts
+        let usage = parse_jsonl_file(log.as_bytes(), path, &UsageFilter::default()).unwrap();
+        assert_eq!(usage.message_count, 2);
+    }
+
+    #[test]
+    fn usage_pre_check_reports_the_same_errors_as_a_full_parse() {
+        let corrupt = [
+            "{\"type\":\"user\",\"message\":{\"content\":\"hi\n",
+            "{\"type\":\"summary\" \"summary\":\"x\"}\n",
+            "not json\n",
+            "{\"message\":{\"usage\":{\"input_tokens\":\n",
+        ];
+        for line in corrupt {
+            let full = serde_json::from_str::<LogEntry>(line).unwrap_err().to_string();
+            assert_eq!(parse_log_line(line).unwrap_err().to_string(), full, "line {:?}", line);
+
+            // ...and so the strict error a log with that line fails with
+            let path = Path::new("session.jsonl");
+            let log = format!("{{}}\n{}", line);
+            let err = parse_jsonl_file(log.as_bytes(), path, &UsageFilter::default().with_strict(true)).unwrap_err();
+            assert_eq!(err.to_string(), format!("Malformed line 2 in {:?}: {}", path, full));
+        }
+
+        // Well-formed lines without usage are skipped, not errors
+        assert!(parse_log_line("{\"type\":\"user\",\"message\":{\"content\":\"hi\"}}\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn parallel_parsing_keeps_file_order() {
+        let files: Vec<PathBuf> = (0..50).map(|i| PathBuf::from(format!("{}.jsonl", i))).collect();
+        let parse = |file: &Path| -> Result<usize> {
+            let index: usize = file.file_stem().unwrap().to_str().unwrap().parse()?;
+            if index == 7 {
+                anyhow::bail!("bad file");
+            }
+            Ok(index)
+        };
+
+        for threads in [1, 4] {
+            let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let results = parse_in_parallel(&pool, &files, parse);
+            assert_eq!(results.len(), 50);
+            assert!(results[7].is_err());
+            assert!(results.iter().enumerate().all(|(i, result)| i == 7 || result.as_ref().ok() == Some(&i)));
+        }
+    }
+
+    #[test]
+    fn unterminated_last_line_is_left_for_later() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-unterminated-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("p1")).unwrap();
+        let log = dir.join("p1").join("session.jsonl");
+        let strict = UsageFilter::default().with_strict(true);
+        let complete = r#"{"message":{"usage":{"input_tokens":10,"output_tokens":5}}}"#;
+        let writing = format!("{}\n{{\"message\":{{\"usage\":{{\"input_t", complete);
+
+        // Live: the half line isn't counted, and doesn't trip --strict, until it's finished
+        std::fs::write(&log, &writing).unwrap();
+        let mut tracker = UsageTracker::new(&dir, &strict);
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().message_count, 1);
+        std::fs::write(&log, format!("{}\n{}\n", complete, complete)).unwrap();
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().message_count, 2);
+
+        // One-shot: nothing will finish the line, so it's malformed like any other
+        let path = Path::new("session.jsonl");
+        assert!(parse_jsonl_file(writing.as_bytes(), path, &strict).is_err());
+        assert_eq!(parse_jsonl_file(writing.as_bytes(), path, &UsageFilter::default()).unwrap().message_count, 1);
+
+        // A finished last line counts even without a trailing newline
+        let usage = parse_jsonl_file(format!("{}\n{}", complete, complete).as_bytes(), path, &strict).unwrap();
+        assert_eq!(usage.message_count, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ignores_entries_without_message_or_usage() {
+        let usage = parse_fixture("missing_fields.jsonl");
+
+        assert_eq!(usage.message_count, 1);
+        assert_eq!(usage.total(), 9);
+    }
+
+    #[test]
+    fn zero_fills_missing_token_fields() {
+        let usage = parse_fixture("partial_usage.jsonl");
+
+        assert_eq!(usage.message_count, 2);
+        assert_eq!(usage.total_input, 100);
+        assert_eq!(usage.total_output, 50);
+        assert_eq!(usage.total_cache_creation, 0);
+        assert_eq!(usage.total_cache_read, 0);
+    }
+
+    #[test]
+    fn empty_input_yields_zero_usage() {
+        let usage = parse_jsonl_file(reader(""), Path::new("empty.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(usage.message_count, 0);
+        assert_eq!(usage.total(), 0);
+    }
+
+    #[test]
+    fn counts_only_lines_with_usage() {
+        let usage = parse_jsonl_file(reader(MIXED_LOG), Path::new("mixed.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(usage.message_count, 2);
+        assert_eq!(usage.total_input, 107);
+        assert_eq!(usage.total_output, 23);
+        assert_eq!(usage.total_cache_creation, 300);
+        assert_eq!(usage.total_cache_read, 4000);
+    }
+
+    #[test]
+    fn counts_reasoning_tokens_separately_and_bills_them_as_output() {
+        let log = r#"{"message":{"usage":{"input_tokens":10,"output_tokens":20,"reasoning_tokens":1000000}}}
+{"message":{"usage":{"input_tokens":5,"output_tokens":5}}}
+"#;
+        let usage = parse_jsonl_file(reader(log), Path::new("reasoning.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(usage.total_reasoning, 1_000_000);
+        assert_eq!(usage.total_output, 25);
+        assert_eq!(usage.total(), 1_000_040);
+
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+        let without_reasoning = AggregatedUsage { total_reasoning: 0, ..usage.clone() };
+        let reasoning_cost = usage.estimated_cost(&pricing) - without_reasoning.estimated_cost(&pricing);
+        assert!((reasoning_cost - pricing.output).abs() < 1e-9);
+    }
+
+    #[test]
+    fn splits_cache_writes_by_lifetime_and_prices_each() {
+        let log = r#"{"message":{"usage":{"input_tokens":0,"output_tokens":0,"cache_creation_input_tokens":3000000,"cache_creation":{"ephemeral_5m_input_tokens":2000000,"ephemeral_1h_input_tokens":1000000}}}}
+{"message":{"usage":{"input_tokens":0,"output_tokens":0,"cache_creation_input_tokens":1000000}}}
+"#;
+        let usage = parse_jsonl_file(reader(log), Path::new("cache.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(usage.total_cache_creation, 4_000_000);
+        assert_eq!(usage.total_cache_creation_1h, 1_000_000);
+        assert_eq!(usage.cache_creation_5m(), 3_000_000);
+
+        // Three million 5-minute writes at $3.75 plus one million 1-hour writes at $6
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+        assert!((usage.estimated_cost(&pricing) - (3.0 * 3.75 + 6.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn breakdowns_add_up_to_the_total() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-breakdowns-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for project in ["p1", "p2"] {
+            std::fs::create_dir_all(dir.join(project)).unwrap();
+            std::fs::write(dir.join(project).join("log.jsonl"), MIXED_LOG).unwrap();
+        }
+
+        let mut by_model = UsageTracker::new(&dir, &UsageFilter::default()).with_key(LogEntry::model_key);
+        by_model.refresh().unwrap();
+        let total = by_model.total();
+        let models: Vec<AggregatedUsage> = by_model.buckets().into_values().collect();
+        let projects: Vec<AggregatedUsage> = by_model.by_project().into_values().collect();
+        total.assert_consistent(&models).unwrap();
+        total.assert_consistent(&projects).unwrap();
+
+        let error = total.assert_consistent(&models[1..]).unwrap_err().to_string();
+        assert!(error.contains("messages"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pre_check_matches_full_deserialization() {
+        // Aggregate every line the slow way and compare with the fast path
+        let mut expected = AggregatedUsage::default();
+        for line in MIXED_LOG.lines().filter(|l| !l.trim().is_empty()) {
+            let entry: LogEntry = serde_json::from_str(line).unwrap();
+            if let Some(usage) = entry.message.and_then(|m| m.usage) {
+                expected.add(&usage);
+            }
+        }
+
+        let actual = parse_jsonl_file(reader(MIXED_LOG), Path::new("mixed.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(actual.total(), expected.total());
+        assert_eq!(actual.message_count, expected.message_count);
+    }
+
+    #[test]
+    fn buckets_usage_by_agent_with_main_for_missing_ids() {
+        let log = r#"{"message":{"usage":{"input_tokens":10,"output_tokens":1}}}
+{"agentId":"a1b2c3","message":{"usage":{"input_tokens":20,"output_tokens":2}}}
+{"agentId":"a1b2c3","message":{"usage":{"input_tokens":30,"output_tokens":3}}}
+"#;
+        let agents = parse_jsonl_file_by_agent(reader(log), Path::new("agents.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[MAIN_AGENT].total(), 11);
+        assert_eq!(agents["a1b2c3"].message_count, 2);
+        assert_eq!(agents["a1b2c3"].total(), 55);
+        assert_eq!(agents.values().sum::<AggregatedUsage>().total(), 66);
+    }
+
+    #[test]
+    fn batch_tier_entries_cost_half_and_missing_tier_is_standard() {
+        let log = r#"{"message":{"usage":{"input_tokens":1000000,"output_tokens":0}}}
+{"message":{"usage":{"input_tokens":1000000,"output_tokens":0,"service_tier":"batch"}}}
+"#;
+        let usage = parse_jsonl_file(reader(log), Path::new("tiers.jsonl"), &UsageFilter::default()).unwrap();
+        let pricing = crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL);
+
+        assert_eq!(usage.total_input, 2_000_000);
+        assert!((usage.estimated_cost(&pricing) - pricing.input * 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn buckets_usage_by_model() {
+        let models = parse_jsonl_file_by_model(reader(MIXED_LOG), Path::new("mixed.jsonl"), &UsageFilter::default()).unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models["claude-sonnet-4-5"].total(), 100 + 20 + 300 + 4000);
+        assert_eq!(models["claude-opus-4-1"].total(), 10);
+    }
+
+    #[test]
+    fn since_filter_drops_older_and_untimestamped_entries() {
+        let since = DateTime::parse_from_rfc3339("2025-12-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let filter = UsageFilter::default().with_since(Some(since));
+        let usage = parse_jsonl_file(reader(MIXED_LOG), Path::new("mixed.jsonl"), &filter).unwrap();
+
+        assert_eq!(usage.message_count, 1);
+        assert_eq!(usage.total_input, 100);
+    }
+
+    #[test]
+    fn project_lookup_accepts_encoded_path_or_repo_name() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-projects-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in ["-home-me-my-repo", "-home-me-other-repo", "-home-me-work-repo"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+
+        assert_eq!(find_project_dir(&dir, "my-repo").unwrap(), dir.join("-home-me-my-repo"));
+        assert_eq!(find_project_dir(&dir, "/home/me/my-repo").unwrap(), dir.join("-home-me-my-repo"));
+        assert_eq!(find_project_dir(&dir, "-home-me-work-repo").unwrap(), dir.join("-home-me-work-repo"));
+        assert!(find_project_dir(&dir, "repo").is_err());
+        assert!(find_project_dir(&dir, "missing").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tracker_reads_only_appended_lines_and_resets_on_truncation() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-tracker-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("p1")).unwrap();
+        let log = dir.join("p1").join("session.jsonl");
+        let line = |input: u64| {
+            format!(r#"{{"type":"assistant","message":{{"usage":{{"input_tokens":{},"output_tokens":0}}}}}}"#, input)
+        };
+        let append = |text: &str| {
+            use std::io::Write;
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&log).unwrap();
+            file.write_all(text.as_bytes()).unwrap();
+        };
+
+        let mut tracker = UsageTracker::new(&dir, &UsageFilter::default());
+        append(&format!("{}\n", line(10)));
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().total_input, 10);
+
+        // A half-written line is left for the next refresh
+        let second = line(5);
+        let (head, tail) = second.split_at(20);
+        append(head);
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().message_count, 1);
+
+        append(&format!("{}\n", tail));
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().total_input, 15);
+        assert_eq!(tracker.by_project()["p1"].message_count, 2);
+
+        // Rotated to a shorter file: start over instead of double counting
+        std::fs::write(&log, format!("{}\n", line(1))).unwrap();
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.total().total_input, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn current_session_tracker_follows_the_newest_session() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-session-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("p1")).unwrap();
+        let log = dir.join("p1").join("log.jsonl");
+        let line = |session: &str, input: u64| {
+            format!(
+                r#"{{"sessionId":"{}","message":{{"usage":{{"input_tokens":{},"output_tokens":0}}}}}}"#,
+                session, input
+            )
+        };
+
+        // No session IDs at all: everything counts
+        std::fs::write(&log, r#"{"message":{"usage":{"input_tokens":7,"output_tokens":0}}}"#.to_string() + "\n").unwrap();
+        let mut tracker = UsageTracker::new(&dir, &UsageFilter::default().with_current_session(true));
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.session(), None);
+        assert_eq!(tracker.total().total_input, 7);
+
+        std::fs::write(&log, format!("{}\n{}\n", line("a", 10), line("b", 5))).unwrap();
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.session(), Some("b"));
+        assert_eq!(tracker.total().total_input, 5);
+
+        std::fs::write(&log, format!("{}\n{}\n{}\n", line("a", 10), line("b", 5), line("c", 1))).unwrap();
+        tracker.refresh().unwrap();
+        assert_eq!(tracker.session(), Some("c"));
+        assert_eq!(tracker.total().total_input, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walks_symlink_cycles_and_duplicate_links_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-symlinks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("app").join("nested")).unwrap();
+        std::fs::write(dir.join("app").join("session.jsonl"), "").unwrap();
+        symlink(dir.join("app"), dir.join("app").join("nested").join("loop")).unwrap();
+        symlink(dir.join("app"), dir.join("app-alias")).unwrap();
+        symlink(dir.join("missing"), dir.join("broken")).unwrap();
+
+        let files = find_jsonl_files(&dir, &UsageFilter::default()).unwrap();
+        assert_eq!(files.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn log_watcher_flags_writes_to_logs_but_not_other_files() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+
+        let watcher = LogWatcher::new(&dir).unwrap();
+        std::fs::write(dir.join("app").join("notes.txt"), "ignored").unwrap();
+        std::thread::sleep(WATCH_DEBOUNCE);
+        assert!(!watcher.changed());
+
+        std::fs::write(dir.join("app").join("session.jsonl"), "{}\n").unwrap();
+        let deadline = Instant::now() + std::time::Duration::from_secs(5);
+        while !watcher.changed() {
+            assert!(Instant::now() < deadline, "no change event for a new log file");
+            std::thread::sleep(WATCH_DEBOUNCE);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn encoded_project_names_follow_the_path_on_disk() {
+        let root = std::env::temp_dir().join(format!("claude-token-counter-decode-{}", std::process::id()));
+        for dir in ["home/me/my-repo", "home/me/.config/tool", "src/a/b", "src/a-b"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        assert_eq!(decode_project_dir(&root, "-home-me-my-repo"), [root.join("home/me/my-repo")]);
+        assert_eq!(decode_project_dir(&root, "-home-me--config-tool"), [root.join("home/me/.config/tool")]);
+        assert_eq!(decode_project_dir(&root, "-src-a-b").len(), 2);
+        assert!(decode_project_dir(&root, "-home-me-deleted-repo").is_empty());
+
+        assert_eq!(readable_project_name(UNKNOWN_PROJECT), UNKNOWN_PROJECT);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn exclude_globs_skip_matching_project_directories() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-exclude-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in ["-home-me-app", "-tmp-scratch-1", "-tmp-scratch-2"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("session.jsonl"), "").unwrap();
+        }
+
+        let filter = UsageFilter::default().with_exclude(&["*scratch*".to_string()]).unwrap();
+        let files = find_jsonl_files(&dir, &filter).unwrap();
+        assert_eq!(files, vec![dir.join("-home-me-app").join("session.jsonl")]);
+        assert_eq!(excluded_projects(&dir, &filter).unwrap(), ["-tmp-scratch-1", "-tmp-scratch-2"]);
+        assert!(UsageFilter::default().with_exclude(&["[".to_string()]).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormattedFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Most verbose level for a `-v` count: warnings only by default, then info, debug, trace
+/// (trace also lets through dependencies' own diagnostics)
+pub fn level_for(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Send log events at `level` and above to stderr for the rest of the process
+/// Warnings read "Warning: ..." as they always have; more verbose levels are
+/// prefixed with their level, target and enclosing spans
+pub fn init(level: LevelFilter) {
+    let _ = tracing::subscriber::set_global_default(subscriber(level, std::io::stderr));
+}
+
+/// Subscriber writing one line per event at `level` and above to `writer`
+fn subscriber<W>(level: LevelFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    // Dependencies (hyper, reqwest) only chime in below warnings at -vvv
+    let dependencies = if level == LevelFilter::TRACE { level } else { level.min(LevelFilter::WARN) };
+    let targets = Targets::new()
+        .with_default(dependencies)
+        .with_target(env!("CARGO_CRATE_NAME"), level);
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer)
+        .event_format(LineFormat { verbose: level > LevelFilter::WARN })
+        .finish()
+        .with(targets)
+}
+
+/// "Warning: message" by default; "LEVEL target spans: message k=v" when verbose
+struct LineFormat {
+    verbose: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for LineFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+        if !self.verbose && *metadata.level() <= Level::WARN {
+            // At the default level, warnings look like the rest of the tool's output
+            write!(writer, "Warning: ")?;
+        } else {
+            write!(writer, "{:>5} {}", metadata.level(), metadata.target())?;
+
+            // "outer{a=1}:inner{b=2}" for the spans the event is in
+            if let Some(scope) = ctx.event_scope() {
+                let spans: Vec<String> = scope
+                    .from_root()
+                    .map(|span| {
+                        let extensions = span.extensions();
+                        match extensions.get::<FormattedFields<N>>().filter(|fields| !fields.is_empty()) {
+                            Some(fields) => format!("{}{{{}}}", span.name(), fields),
+                            None => span.name().to_string(),
+                        }
+                    })
+                    .collect();
+                write!(writer, " {}", spans.join(":"))?;
+            }
+            write!(writer, ": ")?;
+        }
+
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Writer that keeps everything in a shared buffer for inspection
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(level: LevelFilter, log: impl FnOnce()) -> String {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        tracing::subscriber::with_default(subscriber(level, move || writer.clone()), log);
+        let bytes = captured.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn warnings_keep_their_plain_form_by_default() {
+        let output = capture(level_for(0), || {
+            tracing::warn!("Failed to parse {:?}", "a.jsonl");
+            tracing::debug!("hidden");
+        });
+
+        assert_eq!(output, "Warning: Failed to parse \"a.jsonl\"\n");
+    }
+
+    #[test]
+    fn verbose_events_show_level_target_and_spans() {
+        let output = capture(level_for(2), || {
+            let _span = tracing::debug_span!("parse_jsonl_file", path = %"a.jsonl").entered();
+            tracing::debug!(lines = 3, "parsed");
+            tracing::debug!(target: "hyper", "hidden");
+            tracing::trace!("hidden");
+        });
+
+        assert_eq!(
+            output,
+            format!("DEBUG {} parse_jsonl_file{{path=a.jsonl}}: parsed lines=3\n", module_path!())
+        );
+    }
+}
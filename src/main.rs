@@ -1,11 +1,12 @@
 use clap::{Parser, Subcommand};
 use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-mod api;
-mod config;
-mod display;
-mod local;
-mod models;
+use claude_token_counter::util::format_number;
+use claude_token_counter::{api, cache, config, display, doctor, estimate, local, logging, metrics, models, pricing, tui, util};
 
 #[derive(Parser)]
 #[command(name = "claude-token-counter")]
@@ -13,89 +14,555 @@ mod models;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Always query the API instead of using a recently cached response
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Named config profile to use (defaults to the configured default profile)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Config file to use (overrides CLAUDE_TOKEN_COUNTER_CONFIG and the default location)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Suppress informational messages (progress, "saved to" notices); errors still go to stderr
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Log what the tool is doing to stderr: -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Parse log files on at most N threads (default: RAYON_NUM_THREADS, else one per CPU)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: Option<u32>,
+
+    /// Also show prompt tokens as one input-equivalent figure, with cache reads scaled
+    /// down to their discounted rate (in `status` and `live` totals)
+    #[arg(long, global = true)]
+    effective_tokens: bool,
+
+    /// Show costs in this currency (e.g. EUR) instead of USD
+    #[arg(long, global = true)]
+    currency: Option<String>,
+
+    /// Units of --currency per US dollar (defaults to `fx_rate` in the config file)
+    #[arg(long, global = true)]
+    fx_rate: Option<f64>,
+
+    /// Show large token counts abbreviated (1.2M, 345K) instead of exact
+    #[arg(long, global = true)]
+    abbrev: bool,
+
+    /// Show project directories as Claude Code names them (e.g. -home-me-repo) instead of decoded paths
+    #[arg(long, global = true)]
+    raw_names: bool,
+
+    /// Print token counts as raw digits, without separators (overrides --abbrev and `thousands_sep`)
+    #[arg(long, global = true)]
+    plain_numbers: bool,
+
+    /// Send API requests here instead of https://api.anthropic.com/v1 (e.g. an LLM gateway)
+    #[arg(long, global = true, value_name = "URL")]
+    api_base_url: Option<String>,
+
+    /// Scope API usage reports to this workspace instead of the whole organization
+    /// (overrides `workspace_id` in the config file)
+    #[arg(long, global = true, value_name = "ID")]
+    workspace_id: Option<String>,
+
+    /// Read the API key from the first line of this file (overrides
+    /// CLAUDE_TOKEN_COUNTER_API_KEY and the config file)
+    #[arg(long, global = true, value_name = "PATH")]
+    api_key_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Display current token usage and remaining quota
-    Status,
+    Status {
+        /// Keep refreshing every N seconds (at least 10), waiting out rate limits
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(10..))]
+        watch: Option<u64>,
+
+        /// Count cache tokens in the total and against the monthly limit (or set `include_cache` in the config file)
+        #[arg(long)]
+        include_cache: bool,
+
+        /// Also show today's usage against this many tokens (overrides the profile's daily limit)
+        #[arg(long)]
+        daily_limit: Option<u64>,
+
+        /// Records requested per API page (1-1000)
+        #[arg(long, default_value_t = api::MAX_PAGE_LIMIT)]
+        limit: u32,
+
+        /// Also forecast month-end usage from weekday patterns, with a likely range
+        #[arg(long)]
+        forecast: bool,
+
+        /// Print the API's response body as-is, one page per line, instead of the summary
+        #[arg(long, conflicts_with = "watch")]
+        raw: bool,
+
+        /// Print one line for a shell prompt, e.g. "claude: 2.3M/5M (46%) $7.12" (colored only on a terminal)
+        #[arg(long, conflicts_with_all = ["watch", "raw", "forecast", "daily_limit"])]
+        compact: bool,
+    },
     /// Show usage history over time
     History {
         /// Number of days to show (default: 30)
         #[arg(short, long, default_value_t = 30)]
         days: u32,
+
+        /// Show only the N highest-usage days, sorted by total tokens
+        #[arg(long)]
+        top: Option<usize>,
+
+        /// Add estimated cost and share-of-monthly-limit columns
+        #[arg(long)]
+        show_cost: bool,
+
+        /// Bucket rows by day, ISO week, or calendar month (default: day)
+        #[arg(long, value_enum, default_value_t = models::Grouping::Day, conflicts_with = "top")]
+        group_by: models::Grouping,
+
+        /// Show each bucket's full start and end time (useful for hourly buckets)
+        #[arg(long)]
+        show_times: bool,
+
+        /// Sort rows by date or total tokens (default: date)
+        #[arg(long, value_enum, default_value_t = models::SortKey::Date, conflicts_with = "top")]
+        sort: models::SortKey,
+
+        /// Sort direction (default: desc, newest or largest first)
+        #[arg(long, value_enum, default_value_t = models::SortOrder::Desc, conflicts_with = "top")]
+        order: models::SortOrder,
+
+        /// Show a weekday-by-week heatmap of token usage instead of a table
+        #[arg(long, conflicts_with_all = ["top", "group_by", "show_cost", "show_times", "sort", "order"])]
+        heatmap: bool,
+
+        /// Show buckets the API returned with no results as zero rows (hidden by default)
+        #[arg(long)]
+        show_empty: bool,
+
+        /// Print rows oldest first as JSON, with per-row and cumulative cost (USD)
+        #[arg(long, conflicts_with_all = ["top", "heatmap", "show_cost", "show_times", "sort", "order"])]
+        json: bool,
+
+        /// Render the table for the terminal or as Markdown, or stream the --json rows as JSON Lines (default: table)
+        #[arg(long, value_enum, default_value_t = models::HistoryFormat::Table, conflicts_with_all = ["top", "heatmap", "json"])]
+        format: models::HistoryFormat,
+
+        /// Records requested per API page (1-1000)
+        #[arg(long, default_value_t = api::MAX_PAGE_LIMIT)]
+        limit: u32,
+
+        /// Print the API's response body as-is, one page per line, instead of the history
+        #[arg(long, conflicts_with_all = ["top", "heatmap", "json", "format"])]
+        raw: bool,
     },
     /// Configure API key and subscription details
     Config {
-        /// Claude API key
-        #[arg(long)]
+        /// Claude API key for the selected profile
+        #[arg(long, conflicts_with_all = ["clear", "list"])]
         api_key: Option<String>,
+
+        /// Monthly token limit for the selected profile
+        #[arg(long, conflicts_with_all = ["clear", "list"])]
+        monthly_limit: Option<u64>,
+
+        /// Daily token limit for the selected profile, shown by `status` next to the monthly one
+        #[arg(long, conflicts_with_all = ["clear", "list"])]
+        daily_limit: Option<u64>,
+
+        /// Delete the saved config file (including all stored API keys)
+        #[arg(long, conflicts_with = "list")]
+        clear: bool,
+
+        /// List configured profiles (keys masked)
+        #[arg(long)]
+        list: bool,
     },
     /// Monitor Claude Code token usage in real-time from local JSONL files
     Live {
-        /// Refresh interval in seconds (default: 2)
-        #[arg(short, long, default_value_t = 2)]
-        refresh: u64,
+        /// Refresh interval in seconds, fractions allowed (default: 2; minimum 1 unless
+        /// lowered with `refresh_floor` in the config file)
+        #[arg(short, long, default_value_t = 2.0, value_parser = parse_refresh)]
+        refresh: f64,
+
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+
+        /// Break usage down by calendar month, highlighting the current month
+        #[arg(long)]
+        monthly: bool,
+
+        /// Only count entries whose model name contains this text (case-insensitive)
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Only read logs for this project (directory name, original path, or repo name)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Skip project directories whose name matches this glob (repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+
+        /// Break usage down by subagent (entries without an agent ID count as "main")
+        #[arg(long)]
+        by_agent: bool,
+
+        /// Break usage and cost down by model, pricing each model at its own rates
+        #[arg(long)]
+        by_model: bool,
+
+        /// Append a timestamped JSON line with the totals to this file on every refresh
+        #[arg(long, alias = "output-file")]
+        log_file: Option<PathBuf>,
+
+        /// Print a single snapshot and exit instead of refreshing (no terminal control)
+        #[arg(long)]
+        once: bool,
+
+        /// With --once, print the snapshot as a JSON object instead of text
+        #[arg(long, requires = "once")]
+        json: bool,
+
+        /// Redraw when a log file changes instead of every --refresh seconds
+        /// (falls back to polling where filesystem watching isn't available)
+        #[arg(long, conflicts_with = "once")]
+        watch_file: bool,
+
+        /// Fail on the first malformed log line instead of skipping it with a summary warning
+        #[arg(long)]
+        strict: bool,
+
+        /// Count the newest session only (`current`, by the logs' sessionId) or everything logged (`all`)
+        #[arg(long, value_enum, default_value_t = models::SessionScope::All)]
+        session: models::SessionScope,
+
+        /// Check on every refresh that each breakdown adds up to the total, flagging any mismatch
+        /// (implies --plain)
+        #[arg(long)]
+        debug: bool,
+
+        /// Stop after this many seconds and print the final summary
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "once")]
+        duration: Option<u64>,
+
+        /// Use the plain scrolling text view instead of the interactive TUI
+        /// (implied by --monthly, --by-agent, --by-model, --log-file, --debug, or a non-terminal stdout)
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Compare usage and cost between two calendar months
+    Compare {
+        /// First month to compare (YYYY-MM)
+        #[arg(long, value_parser = parse_month)]
+        period_a: NaiveDate,
+
+        /// Second month to compare (YYYY-MM)
+        #[arg(long, value_parser = parse_month)]
+        period_b: NaiveDate,
+    },
+    /// Check usage against a token budget and exit non-zero past the warning threshold
+    #[command(after_help = "Exit codes:\n  \
+        0  Usage is below the warning threshold\n  \
+        1  Usage is at or over the limit\n  \
+        2  Usage is between the warning threshold and the limit")]
+    Budget {
+        /// Token limit to check usage against
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        limit: u64,
+
+        /// Warning threshold as a percentage of the limit (default: 80)
+        #[arg(long, default_value_t = 80.0, value_parser = parse_warn)]
+        warn: f64,
+
+        /// Count cache tokens against the limit (or set `include_cache` in the config file)
+        #[arg(long)]
+        include_cache: bool,
+    },
+    /// Compare API-billed usage with usage observed in local Claude Code logs
+    Summary {
+        /// Number of days to cover (default: 30)
+        #[arg(short, long, default_value_t = 30)]
+        days: u32,
+
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+    },
+    /// Show what Anthropic billed, from the cost report, next to our usage-based estimate
+    Cost {
+        /// Number of days to cover (default: 30)
+        #[arg(short, long, default_value_t = 30)]
+        days: u32,
+    },
+    /// Print a pasteable plain-text usage report comparing this period with the last
+    Report {
+        /// Report on the last 7 days or the last 30 days
+        #[arg(long, value_enum, default_value_t = models::ReportPeriod::Week)]
+        period: models::ReportPeriod,
+
+        /// Claude Code projects directory used to find the top project (optional)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+    },
+    /// Print local usage as Prometheus text-format gauges (for a textfile collector or sidecar)
+    Metrics {
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+
+        /// Label samples with the project directory name
+        #[arg(long)]
+        by_project: bool,
+
+        /// Label samples with the model, pricing each model at its own rates
+        #[arg(long)]
+        by_model: bool,
+    },
+    /// Rank projects by local token usage or estimated cost
+    TopProjects {
+        /// Rank by total tokens or estimated cost (default: tokens)
+        #[arg(long, value_enum, default_value_t = models::RankBy::Tokens)]
+        by: models::RankBy,
+
+        /// Number of projects to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+    },
+    /// Estimate the token count and input cost of some text before sending it
+    Count {
+        /// Text to estimate
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        text: Option<String>,
+
+        /// Read the text from this file ("-" for stdin)
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Model whose input rate prices the estimate
+        #[arg(long, default_value = pricing::DEFAULT_MODEL)]
+        model: String,
+    },
+    /// Show the version, config path, API key (masked), and where local logs are read from
+    #[command(visible_alias = "version")]
+    Info {
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
+    },
+    /// Check the config, API key, and Claude Code logs for common setup problems
+    Doctor {
+        /// Claude Code projects directory (overrides CLAUDE_PROJECTS_DIR and ~/.claude/projects)
+        #[arg(long)]
+        projects_dir: Option<PathBuf>,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    logging::init(logging::level_for(cli.verbose));
+    display::set_quiet(cli.quiet);
+    local::set_raw_project_names(cli.raw_names);
+    display::set_effective_tokens(cli.effective_tokens);
+    if let Some(threads) = cli.concurrency {
+        local::set_parse_concurrency(threads as usize);
+    }
+    let cache_ttl = (!cli.no_cache).then_some(cache::DEFAULT_TTL);
+    let config_path = cli.config.as_deref();
+
+    // Settings that apply to every command; a missing config just means defaults, but a
+    // broken one is an error, except for the commands that inspect or remove it
+    let settings = match cli.command {
+        Commands::Config { clear: true, .. } | Commands::Doctor { .. } | Commands::Info { .. } => {
+            config::Config::load(config_path).unwrap_or_default()
+        }
+        _ => config::Config::load_or_default(config_path)?,
+    };
+
+    // Costs are computed in USD and converted at a user-supplied rate for display
+    display::set_currency(settings.currency(cli.currency.as_deref(), cli.fx_rate)?);
+    util::set_number_format(settings.number_format(cli.abbrev, cli.plain_numbers)?);
+
+    let api_base_url = cli.api_base_url.clone().or(settings.api_base_url.clone());
+    let workspace_id = cli.workspace_id.clone().or(settings.workspace_id.clone());
 
     match cli.command {
-        Commands::Status => {
-            // Load config to get API key
-            let config = config::Config::load()
-                .context("No API key configured. Run 'config --api-key YOUR_KEY' first")?;
+        Commands::Status { watch, include_cache, daily_limit, limit, forecast, raw, compact } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+
+            // Uses the profile's monthly limit, falling back to 5M tokens (typical for Claude Pro)
+            let monthly_limit = Some(profile.monthly_limit.unwrap_or(DEFAULT_MONTHLY_LIMIT));
+            let daily_limit = daily_limit.or(profile.daily_limit);
+            let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+            let include_cache = include_cache || config.include_cache.unwrap_or(false);
+
+            // Watching wants fresh numbers every time, so it skips the cache
+            if let Some(interval) = watch {
+                let client = api::AnthropicClient::new(profile.api_key)?
+                    .with_cache_ttl(None)
+                    .with_base_url(api_base_url.as_deref())?
+                    .with_workspace_id(workspace_id.as_deref())?
+                    .with_page_limit(limit)?;
+                let view = StatusView { monthly_limit, daily_limit, include_cache, forecast, thresholds: config.color_thresholds() };
+                return run_status_watch(&client, interval, view, &model_pricing).await;
+            }
 
             // Create API client
-            let client = api::AnthropicClient::new(config.api_key)?;
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?
+                .with_page_limit(limit)?;
+
+            if raw {
+                return print_raw_usage(&client, 30).await;
+            }
+
+            // Prompts redraw often: no spinner, no personal-best bookkeeping, just the line
+            if compact {
+                use std::io::IsTerminal;
 
-            println!("Fetching usage data from Anthropic API...");
+                let usage_response = client.fetch_usage(30).await?;
+                let summary = models::UsageSummary::from_records(&usage_response.data).with_cache_in_total(include_cache);
+                let color = std::io::stdout().is_terminal();
+                let limit = profile.monthly_limit.unwrap_or(DEFAULT_MONTHLY_LIMIT);
+                println!("{}", display::format_compact_status(&summary, limit, &model_pricing, config.color_thresholds(), color));
+                return Ok(());
+            }
 
             // Fetch usage data (last 30 days)
-            let usage_response = client.fetch_usage(30).await?;
+            let usage_response = display::with_spinner(
+                "Fetching usage data from Anthropic API...",
+                client.fetch_usage(30),
+            ).await?;
 
             // Calculate summary
-            let summary = models::UsageSummary::from_records(&usage_response.data);
+            let summary = models::UsageSummary::from_records(&usage_response.data).with_cache_in_total(include_cache);
+            let today = daily_limit.map(|_| today_summary(&usage_response.data, include_cache));
+            let daily = today.as_ref().zip(daily_limit);
+
+            let forecast = forecast.then(|| month_forecast(&usage_response.data, include_cache));
+            let bests = record_personal_bests(&client, &usage_response.data, &model_pricing);
 
             // Display results with beautiful formatting
-            // Note: Set your monthly limit here (in tokens)
-            // For Claude Pro: typically 5M tokens/month
-            let monthly_limit = Some(5_000_000); // Adjust this to your actual limit
-            display::display_status(&summary, monthly_limit);
+            display::display_status(&summary, monthly_limit, daily, forecast.as_ref(), bests.as_ref(), &model_pricing, config.color_thresholds());
         }
-        Commands::History { days } => {
-            // Load config to get API key
-            let config = config::Config::load()
-                .context("No API key configured. Run 'config --api-key YOUR_KEY' first")?;
+        Commands::History { days, top, show_cost, group_by, show_times, sort, order, show_empty, heatmap, json, format, limit, raw } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
 
             // Create API client
-            let client = api::AnthropicClient::new(config.api_key)?;
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?
+                .with_page_limit(limit)?;
 
-            println!("Fetching usage history from Anthropic API...");
+            if raw {
+                return print_raw_usage(&client, days).await;
+            }
 
             // Fetch usage data
-            let usage_response = client.fetch_usage(days).await?;
+            let usage_response = display::with_spinner(
+                "Fetching usage history from Anthropic API...",
+                client.fetch_usage(days),
+            ).await?;
 
-            // Display history
-            display::display_history(&usage_response.data, days);
+            // Display history, a heatmap, or just the most expensive days when --top is set
+            let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+            let bests = record_personal_bests(&client, &usage_response.data, &model_pricing);
+            let hide_empty = !show_empty;
+            // The table hides empty buckets itself and reports how many; the JSON outputs just leave them out
+            let shown: Vec<models::UsageRecord> = usage_response.data.iter()
+                .filter(|record| !(hide_empty && record.is_empty()))
+                .cloned()
+                .collect();
+            match top {
+                None if json => {
+                    let rows = models::history_rows(&group_by.group_records(&shown), &model_pricing);
+                    println!("{}", serde_json::to_string_pretty(&rows)?);
+                }
+                Some(n) => display::display_top_days(&usage_response.data, n, &model_pricing),
+                None if heatmap => display::display_heatmap(&usage_response.data, days),
+                None => {
+                    let columns = if show_cost {
+                        display::HistoryColumns {
+                            cost: Some(&model_pricing),
+                            monthly_limit: profile.monthly_limit,
+                            grouping: group_by,
+                            show_times,
+                            thresholds: config.color_thresholds(),
+                            sort,
+                            order,
+                            hide_empty,
+                        }
+                    } else {
+                        display::HistoryColumns {
+                            grouping: group_by,
+                            show_times,
+                            thresholds: config.color_thresholds(),
+                            sort,
+                            order,
+                            hide_empty,
+                            ..Default::default()
+                        }
+                    };
+                    match format {
+                        models::HistoryFormat::Table => display::display_history(&usage_response.data, days, columns, bests.as_ref()),
+                        models::HistoryFormat::Markdown => {
+                            print!("{}", display::format_history_markdown(&usage_response.data, days, columns))
+                        }
+                        models::HistoryFormat::Jsonl => {
+                            let mut records = group_by.group_records(&shown);
+                            let out = std::io::BufWriter::new(std::io::stdout().lock());
+                            models::write_history_jsonl(&mut records, &model_pricing, out)?;
+                        }
+                    }
+                }
+            }
         }
-        Commands::Config { api_key } => {
-            if let Some(key) = api_key {
-                let config = config::Config::new(key);
-                config.save()?;
-                println!("✓ API key configured successfully");
+        Commands::Config { api_key, monthly_limit, daily_limit, clear, list } => {
+            if clear {
+                match config::Config::clear(config_path)? {
+                    Some(path) => println!("✓ Removed config file at {:?}", path),
+                    None => println!("No config file found. Nothing to clear."),
+                }
+            } else if list {
+                let cfg = config::Config::load_or_default(config_path)?;
+                if cfg.profiles.is_empty() {
+                    println!("No profiles configured. Use --api-key to add one.");
+                }
+                let default_name = cfg.profile_name(None);
+                for (name, profile) in &cfg.profiles {
+                    let marker = if name == default_name { "*" } else { " " };
+                    println!("{} {:<16} {}", marker, name, profile.masked_api_key());
+                }
+            } else if api_key.is_some() || monthly_limit.is_some() || daily_limit.is_some() {
+                let name = update_profile(config_path, cli.profile.as_deref(), api_key, monthly_limit, daily_limit)?;
+                println!("✓ Profile '{}' configured successfully", name);
             } else {
                 // Show current config status
-                match config::Config::load() {
-                    Ok(cfg) => {
-                        let masked_key = format!("{}...{}",
-                            &cfg.api_key[..8],
-                            &cfg.api_key[cfg.api_key.len()-4..]
-                        );
-                        println!("API key is configured: {}", masked_key);
+                let config = config::Config::load_or_default(config_path)?;
+                match config.profile(cli.profile.as_deref()) {
+                    Ok(profile) => {
+                        let name = config.profile_name(cli.profile.as_deref());
+                        println!("API key is configured for profile '{}': {}", name, profile.masked_api_key());
                     }
                     Err(_) => {
                         println!("No API key configured. Use --api-key to set one.");
@@ -103,106 +570,1195 @@ async fn main() -> Result<()> {
                 }
             }
         }
-        Commands::Live { refresh } => {
+        Commands::Compare { period_a, period_b } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+
+            // Create API client
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?;
+
+            let (response_a, response_b) = display::with_spinner(
+                "Fetching usage for both periods from Anthropic API...",
+                async {
+                    let response_a = client.fetch_usage_range(period_a, next_month(period_a)).await?;
+                    let response_b = client.fetch_usage_range(period_b, next_month(period_b)).await?;
+                    Ok::<_, api::ApiError>((response_a, response_b))
+                },
+            ).await?;
+
+            let summary_a = models::UsageSummary::from_records(&response_a.data);
+            let summary_b = models::UsageSummary::from_records(&response_b.data);
+
+            let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+            display::display_comparison(
+                (&period_a.format("%Y-%m").to_string(), &summary_a),
+                (&period_b.format("%Y-%m").to_string(), &summary_b),
+                &model_pricing,
+            );
+        }
+        Commands::Budget { limit, warn, include_cache } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+
+            // Create API client
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?;
+
+            // Fetch usage data (last 30 days)
+            let usage_response = client.fetch_usage(30).await?;
+            let include_cache = include_cache || config.include_cache.unwrap_or(false);
+            let summary = models::UsageSummary::from_records(&usage_response.data).with_cache_in_total(include_cache);
+
+            let percentage = summary.percentage_used(limit);
+            println!("{:.1}% of budget used ({} / {} tokens)",
+                percentage,
+                format_number(summary.total_tokens),
+                format_number(limit)
+            );
+
+            let exit_code = budget_exit_code(percentage, warn);
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
+        Commands::Summary { days, projects_dir } => {
+            // Count local entries from the same start date the API query uses
+            let since = (chrono::Utc::now() - chrono::Duration::days(i64::from(days)))
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .map(|midnight| midnight.and_utc());
+            let parse_local = tokio::task::spawn_blocking(move || {
+                let projects_dir = local::get_claude_projects_dir(projects_dir.as_deref())?;
+                local::parse_all_files(&projects_dir, &local::UsageFilter::default().with_since(since))
+            });
+
+            // Without a usable profile the API side fails like a fetch would, leaving the local side
+            let fetch_api = async {
+                let (_, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+                let client = api::AnthropicClient::new(profile.api_key)?
+                    .with_cache_ttl(cache_ttl)
+                    .with_base_url(api_base_url.as_deref())?
+                    .with_workspace_id(workspace_id.as_deref())?;
+                anyhow::Ok(client.fetch_usage(days).await?)
+            };
+
+            // The network fetch and the log parse overlap instead of running back to back
+            let (usage_response, local_usage) = display::with_spinner(
+                "Fetching usage data from Anthropic API and reading local logs...",
+                async { tokio::join!(fetch_api, parse_local) },
+            ).await;
+
+            // Either side alone is still worth showing; only give up when both fail
+            let api_totals = match usage_response {
+                Ok(response) => Some(models::SourceTotals::from(&models::UsageSummary::from_records(&response.data))),
+                Err(e) => {
+                    tracing::warn!("Could not fetch API usage: {:#}", e);
+                    None
+                }
+            };
+            let local_totals = match local_usage.map_err(anyhow::Error::from).and_then(|usage| usage) {
+                Ok(usage) => Some(models::SourceTotals::from(&usage)),
+                Err(e) => {
+                    tracing::warn!("Could not read local logs: {:#}", e);
+                    None
+                }
+            };
+            if api_totals.is_none() && local_totals.is_none() {
+                anyhow::bail!("Neither API usage nor local logs could be loaded");
+            }
+
+            let model_pricing = settings.pricing_for(pricing::DEFAULT_MODEL);
+            display::display_source_comparison(
+                days,
+                api_totals.as_ref(),
+                local_totals.as_ref(),
+                &model_pricing,
+            );
+        }
+        Commands::Cost { days } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+
+            // Create API client
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?;
+
+            let (cost_response, usage_response) = display::with_spinner(
+                "Fetching cost and usage reports from Anthropic API...",
+                async { tokio::try_join!(client.fetch_cost(days), client.fetch_usage(days)) },
+            ).await?;
+
+            let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+            let rows = models::daily_costs(&cost_response.data, &usage_response.data, &model_pricing);
+            display::display_cost_comparison(days, &rows);
+        }
+        Commands::Report { period, projects_dir } => {
+            // Load config to get the selected profile's API key
+            let (config, profile) = load_profile(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref())?;
+
+            // Create API client
+            let client = api::AnthropicClient::new(profile.api_key)?
+                .with_cache_ttl(cache_ttl)
+                .with_base_url(api_base_url.as_deref())?
+                .with_workspace_id(workspace_id.as_deref())?;
+
+            // Fetch this period and the one before it in a single query
+            let today = chrono::Utc::now().date_naive();
+            let period_days = chrono::Days::new(u64::from(period.days()));
+            let current_start = today - period_days + chrono::Days::new(1);
+            let previous_start = current_start - period_days;
+            let usage_response = display::with_spinner(
+                "Fetching usage data from Anthropic API...",
+                client.fetch_usage_range(previous_start, today + chrono::Days::new(1)),
+            ).await?;
+
+            let boundary = current_start.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc();
+            let (current, previous) = models::split_records_at(&usage_response.data, boundary);
+            let current = models::UsageSummary::from_records(&current);
+            let previous = models::UsageSummary::from_records(&previous);
+
+            // The API doesn't break usage down by project, so the top project comes
+            // from local logs and is left out when there are none
+            let since_filter = local::UsageFilter::default().with_since(Some(boundary));
+            let top_project = local::get_claude_projects_dir(projects_dir.as_deref())
+                .and_then(|dir| local::parse_all_files_by_project(&dir, &since_filter))
+                .ok()
+                .and_then(|projects| projects.into_iter().max_by_key(|(_, usage)| usage.total()))
+                .filter(|(_, usage)| usage.total() > 0)
+                .map(|(name, usage)| (local::readable_project_name(&name), usage));
+
+            let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+            print!(
+                "{}",
+                display::format_report(
+                    period,
+                    &current,
+                    &previous,
+                    top_project.as_ref().map(|(name, usage)| (name.as_str(), usage.total())),
+                    &model_pricing,
+                )
+            );
+        }
+        Commands::Count { text, file, model } => {
+            let text = match (text, file) {
+                (Some(text), _) => text,
+                (None, Some(path)) if path.as_os_str() == "-" => {
+                    std::io::read_to_string(std::io::stdin()).context("Could not read text from stdin")?
+                }
+                (None, Some(path)) => {
+                    std::fs::read_to_string(&path).with_context(|| format!("Could not read {:?}", path))?
+                }
+                (None, None) => unreachable!("clap requires --text or --file"),
+            };
+
+            let tokens = estimate::estimate_tokens(&text);
+            let cost = pricing::calculate_cost(&settings.pricing_for(&model), tokens, 0, 0, 0);
+            display::display_token_estimate(text.chars().count(), tokens, &model, cost);
+        }
+        Commands::TopProjects { by, limit, projects_dir } => {
+            let projects_dir = local::get_claude_projects_dir(projects_dir.as_deref())?;
+            let mut tracker = local::UsageTracker::new(&projects_dir, &local::UsageFilter::default())
+                .with_key(local::LogEntry::month_model_key);
+            tracker.refresh()?;
+
+            // Projects without a limit of their own fall back to the profile's monthly limit
+            let default_limit = settings.profile(cli.profile.as_deref()).ok().and_then(|p| p.monthly_limit);
+            let this_month = chrono::Local::now().format("%Y-%m").to_string();
+
+            // Price each model at its own rates before summing per project
+            let mut totals: HashMap<String, models::ProjectTotal> = HashMap::new();
+            for ((project, key), usage) in tracker.by_project_and_key() {
+                let (month, model) = local::split_month_model(&key);
+                let total = totals.entry(project.clone()).or_insert_with(|| models::ProjectTotal {
+                    name: local::readable_project_name(&project),
+                    tokens: 0,
+                    cost: 0.0,
+                    month_tokens: 0,
+                    monthly_limit: settings.project_limit(&project).or(default_limit),
+                });
+                total.tokens += usage.total();
+                total.cost += usage.estimated_cost(&settings.pricing_for(model));
+                if month == this_month {
+                    total.month_tokens += usage.total();
+                }
+            }
+
+            let mut projects: Vec<_> = totals.into_values().collect();
+            models::rank_projects(&mut projects, by);
+            display::display_top_projects(&projects, limit, by, &settings.color_thresholds());
+        }
+        Commands::Metrics { projects_dir, by_project, by_model } => {
+            let projects_dir = local::get_claude_projects_dir(projects_dir.as_deref())?;
+            let mut tracker = local::UsageTracker::new(&projects_dir, &local::UsageFilter::default());
+            if by_model {
+                tracker = tracker.with_key(local::LogEntry::model_key);
+            }
+            tracker.refresh()?;
+
+            // Collapse whichever dimensions weren't asked for
+            let mut buckets: HashMap<(String, String), local::AggregatedUsage> = HashMap::new();
+            for ((project, model), usage) in tracker.by_project_and_key() {
+                let project = if by_project { project } else { String::new() };
+                buckets.entry((project, model)).or_default().merge(&usage);
+            }
+
+            let mut samples: Vec<metrics::Sample> = buckets
+                .into_iter()
+                .map(|((project, model), usage)| {
+                    let mut labels = Vec::new();
+                    if by_project {
+                        labels.push(("project", project));
+                    }
+                    let model_pricing = if by_model {
+                        let model_pricing = settings.pricing_for(&model);
+                        labels.push(("model", model));
+                        model_pricing
+                    } else {
+                        settings.pricing_for(pricing::DEFAULT_MODEL)
+                    };
+                    let cost = calculate_cost(&usage, &model_pricing);
+                    metrics::Sample { labels, usage, cost }
+                })
+                .collect();
+            samples.sort_by(|a, b| a.labels.cmp(&b.labels));
+
+            print!("{}", metrics::render(&samples));
+        }
+        Commands::Info { projects_dir } => {
+            let info = doctor::gather_info(config_path, cli.profile.as_deref(), cli.api_key_file.as_deref(), projects_dir.as_deref());
+            display::display_info(&info);
+        }
+        Commands::Doctor { projects_dir } => {
+            let checks = doctor::run_checks(config_path, cli.profile.as_deref(), projects_dir.as_deref());
+            display::display_checklist(&checks);
+
+            if doctor::has_failures(&checks) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Live { refresh, projects_dir, monthly, model, project, exclude, by_agent, by_model, log_file, once, json, watch_file, strict, session, debug, duration, plain } => {
             // Run live monitoring
-            run_live_monitor(refresh).await?;
+            let options = LiveOptions {
+                refresh_seconds: refresh,
+                projects_dir,
+                monthly,
+                by_agent,
+                by_model,
+                filter: local::UsageFilter::default()
+                    .with_model(model)
+                    .with_project(project)
+                    .with_exclude(&exclude)?
+                    .with_strict(strict)
+                    .with_current_session(session == models::SessionScope::Current),
+                log_file,
+                once,
+                json,
+                duration: duration.map(Duration::from_secs),
+                watch_file,
+                debug,
+                plain,
+                config_path: cli.config.clone(),
+            };
+            run_live_monitor(options).await?;
         }
     }
 
     Ok(())
 }
 
-/// Run live monitoring of Claude Code token usage
-async fn run_live_monitor(refresh_seconds: u64) -> Result<()> {
-    use colored::*;
+/// Monthly token limit used when the profile doesn't set one (typical for Claude Pro)
+const DEFAULT_MONTHLY_LIMIT: u64 = 5_000_000;
+
+/// Load the config and clone out the selected profile with its API key resolved
+/// Key precedence: `--api-key-file`, then CLAUDE_TOKEN_COUNTER_API_KEY, then the
+/// profile's `api_key_file`, then its `api_key`. With a key from the first two,
+/// no config file is needed.
+fn load_profile(
+    config_path: Option<&Path>,
+    selected: Option<&str>,
+    api_key_file: Option<&Path>,
+) -> Result<(config::Config, config::Profile)> {
+    let override_key = config::api_key_override(api_key_file)?;
+
+    let config = match config::Config::load(config_path) {
+        Ok(config) => config,
+        Err(_) if override_key.is_some() && !config::Config::config_path(config_path)?.exists() => {
+            config::Config::default()
+        }
+        Err(e) => return Err(e.context("No API key configured. Run 'config --api-key YOUR_KEY' first")),
+    };
+
+    let profile = match (override_key, config.profile(selected)) {
+        (Some(api_key), Ok(profile)) => config::Profile { api_key, api_key_file: None, ..profile.clone() },
+        (Some(api_key), Err(_)) => config::Profile::new(api_key),
+        (None, Ok(profile)) => profile.resolved()?,
+        (None, Err(e)) => return Err(e),
+    };
+    Ok((config, profile))
+}
+
+/// Set the selected profile's key and limits, keeping everything else in the config
+/// (other profiles, pricing overrides); returns the profile's name
+fn update_profile(
+    config_path: Option<&Path>,
+    selected: Option<&str>,
+    api_key: Option<String>,
+    monthly_limit: Option<u64>,
+    daily_limit: Option<u64>,
+) -> Result<String> {
+    let mut config = config::Config::load_or_default(config_path)?;
+    let name = config.profile_name(selected).to_string();
+
+    let profile = match api_key {
+        Some(key) => config.upsert_profile(&name, key),
+        None => config.profiles.get_mut(&name).with_context(|| {
+            format!("No profile named '{}' configured. Set --api-key first", name)
+        })?,
+    };
+    if monthly_limit.is_some() {
+        profile.monthly_limit = monthly_limit;
+    }
+    if daily_limit.is_some() {
+        profile.daily_limit = daily_limit;
+    }
+
+    config.save(config_path)?;
+    Ok(name)
+}
+
+/// Usage in today's (UTC) buckets, for the daily limit
+fn today_summary(records: &[models::UsageRecord], include_cache: bool) -> models::UsageSummary {
+    let today = chrono::Utc::now().date_naive();
+    models::UsageSummary::from_records(&models::records_on(records, today)).with_cache_in_total(include_cache)
+}
+
+/// Weekday-aware forecast of this month's total (UTC) for `status --forecast`
+fn month_forecast(records: &[models::UsageRecord], include_cache: bool) -> models::MonthForecast {
+    models::forecast_month(records, chrono::Utc::now().date_naive(), include_cache)
+}
+
+/// Wait used when a 429 response doesn't say how long to back off
+const DEFAULT_RATE_LIMIT_BACKOFF: u64 = 60;
+
+/// How `status` presents the summary on each redraw
+struct StatusView {
+    monthly_limit: Option<u64>,
+    daily_limit: Option<u64>,
+    include_cache: bool,
+    forecast: bool,
+    thresholds: display::ColorThresholds,
+}
+
+/// Redraw `status` every `interval` seconds until Ctrl+C
+/// A 429 shows a countdown banner for the `Retry-After` period instead of stale numbers;
+/// any other error ends the watch
+async fn run_status_watch(
+    client: &api::AnthropicClient,
+    interval: u64,
+    view: StatusView,
+    model_pricing: &pricing::ModelPricing,
+) -> Result<()> {
     use crossterm::{
         cursor,
         terminal::{self, ClearType},
         ExecutableCommand,
     };
     use std::io::stdout;
-    use std::time::Duration;
 
-    println!("{}", "Starting Claude Code Live Monitor...".bright_cyan().bold());
-    println!("Reading from: ~/.claude/projects/\n");
-    println!("Press Ctrl+C to exit\n");
-
-    tokio::time::sleep(Duration::from_secs(1)).await;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
 
     loop {
-        // Parse all JSONL files
-        let usage = local::parse_all_files()?;
+        let result = tokio::select! {
+            result = client.fetch_usage(30) => result,
+            _ = &mut ctrl_c => return Ok(()),
+        };
 
-        // Clear screen and move cursor to top
         stdout()
             .execute(cursor::MoveTo(0, 0))?
             .execute(terminal::Clear(ClearType::FromCursorDown))?;
 
-        // Display header
-        println!("{}", "═".repeat(70).bright_blue());
-        println!("{}", "  CLAUDE CODE - LIVE TOKEN USAGE".bright_cyan().bold());
-        println!("{}", "═".repeat(70).bright_blue());
-        println!();
+        let wait = match result {
+            Ok(response) => {
+                let summary = models::UsageSummary::from_records(&response.data).with_cache_in_total(view.include_cache);
+                let today = view.daily_limit.map(|_| today_summary(&response.data, view.include_cache));
+                let daily = today.as_ref().zip(view.daily_limit);
+                let forecast = view.forecast.then(|| month_forecast(&response.data, view.include_cache));
+                let bests = record_personal_bests(client, &response.data, model_pricing);
+                display::display_status(&summary, view.monthly_limit, daily, forecast.as_ref(), bests.as_ref(), model_pricing, view.thresholds);
+                display::info(format!("Refreshing every {}s · Press Ctrl+C to exit", interval));
+                interval
+            }
+            Err(api::ApiError::RateLimited { retry_after }) => {
+                let wait = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF);
+                display::display_rate_limited(wait);
+                wait
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(wait)) => {}
+            _ = &mut ctrl_c => return Ok(()),
+        }
+    }
+}
+
+/// Print each page of the usage report exactly as the API returned it (`--raw`)
+/// No progress message, so the output can be piped straight into `jq`
+async fn print_raw_usage(client: &api::AnthropicClient, days: u32) -> Result<()> {
+    for page in client.fetch_usage_raw(days).await? {
+        println!("{}", page);
+    }
+    Ok(())
+}
+
+/// Update the all-time peaks saved for `client`'s profile and workspace with this run's records
+/// A failure only costs the display
+fn record_personal_bests(client: &api::AnthropicClient, records: &[models::UsageRecord], pricing: &pricing::ModelPricing) -> Option<cache::PersonalBests> {
+    cache::PersonalBests::record(records, pricing, &client.scope())
+        .map_err(|e| tracing::warn!("Could not update personal bests: {:#}", e))
+        .ok()
+}
+
+/// Settings for the `live` command
+struct LiveOptions {
+    refresh_seconds: f64,
+    projects_dir: Option<PathBuf>,
+    monthly: bool,
+    by_agent: bool,
+    by_model: bool,
+    filter: local::UsageFilter,
+    log_file: Option<PathBuf>,
+    once: bool,
+    json: bool,
+    /// Wall-clock time after which the monitor stops on its own
+    duration: Option<Duration>,
+    watch_file: bool,
+    /// Check breakdowns against the total on every refresh
+    debug: bool,
+    plain: bool,
+    config_path: Option<PathBuf>,
+}
+
+/// Run live monitoring of Claude Code token usage
+async fn run_live_monitor(options: LiveOptions) -> Result<()> {
+    use colored::*;
+    use crossterm::{
+        cursor,
+        terminal::{self, ClearType},
+        ExecutableCommand,
+    };
+    use std::io::{stdout, IsTerminal, Write};
+
+    let LiveOptions { refresh_seconds, monthly, by_agent, by_model, ref filter, ref log_file, duration, debug, plain, .. } = options;
+
+    // Resolve (and validate) the projects directory once up front
+    let projects_dir = local::get_claude_projects_dir(options.projects_dir.as_deref())?;
+    if let Some(project) = &filter.project {
+        local::find_project_dir(&projects_dir, project)?;
+    }
+
+    // Pricing overrides are optional, so a missing config just means built-in rates
+    let config = config::Config::load_or_default(options.config_path.as_deref())?;
+    let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+
+    // One-shot mode: a single snapshot for scripts, without touching the terminal
+    if options.once {
+        let frame = collect_live_frame(&mut LiveTrackers::new(&projects_dir, &options), &config)?;
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&LiveSnapshot::new(&frame.usage, frame.cost, frame.session.clone()))?);
+        } else {
+            print_live_frame(&frame, filter, &config, None);
+            println!("{}", display::divider(70).bright_blue());
+        }
+        return Ok(());
+    }
+
+    // Refreshing re-walks the projects directory, so keep the interval above the floor
+    let refresh_floor = config.refresh_floor();
+    if refresh_seconds < refresh_floor {
+        anyhow::bail!(
+            "--refresh {} is below the minimum of {} seconds. Use a longer interval, \
+             or lower `refresh_floor` in the config file (down to {}).",
+            refresh_seconds,
+            refresh_floor,
+            config::MIN_REFRESH_FLOOR
+        );
+    }
+    let refresh = Duration::from_secs_f64(refresh_seconds);
+
+    // Event-driven redraws when asked for and the platform supports them
+    let watcher = if options.watch_file {
+        match local::LogWatcher::new(&projects_dir) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::warn!("{:#}; polling every {} seconds instead", e, refresh_seconds);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // The TUI has no monthly, per-agent, per-model, snapshot-log, or debug support, so those imply the plain view
+    let use_tui = !plain && !monthly && !by_agent && !by_model && !debug && log_file.is_none() && stdout().is_terminal();
+    if use_tui {
+        // The TUI only prices at the default model's rates, as it showed them
+        if let Some(last_usage) = tui::run(&projects_dir, refresh, duration, watcher.as_ref(), filter, &model_pricing)? {
+            let cost = calculate_cost(&last_usage, &model_pricing);
+            print_final_summary(&last_usage, cost, &model_pricing);
+            return Ok(());
+        }
+    }
+
+    display::info("Starting Claude Code Live Monitor...".bright_cyan().bold());
+    display::info(format!("Reading from: {}\n", projects_dir.display()));
+    display::info("Press Ctrl+C to exit\n");
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Open the snapshot log once in append mode
+    let mut snapshot_log = match log_file {
+        Some(path) => Some(
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Could not open log file {:?}", path))?,
+        ),
+        None => None,
+    };
+    let mut snapshot_log_failed = false;
+
+    let mut burn_rate = local::BurnRate::default();
+
+    // Redraw in place on a terminal; piped output (e.g. `| tee`) just scrolls
+    let mut redraw_in_place = stdout().is_terminal();
+
+    // Register the Ctrl+C handler once so a signal during parsing is still caught
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    let mut trackers = LiveTrackers::new(&projects_dir, &options);
+    let started = Instant::now();
+    // The last frame's usage and cost (per-model under --by-model), for the exit summary
+    let mut last_frame = (local::AggregatedUsage::default(), 0.0);
+    let (last_usage, last_cost) = 'monitor: loop {
+        if duration.is_some_and(|duration| started.elapsed() >= duration) {
+            break last_frame;
+        }
+
+        let frame = collect_live_frame(&mut trackers, &config)?;
+        let usage = &frame.usage;
+        let cost = frame.cost;
+
+        // Record this refresh in the snapshot log, warning only on the first failure
+        if let Some(file) = snapshot_log.as_mut() {
+            let snapshot = LiveSnapshot::new(usage, cost, frame.session.clone());
+            let written = serde_json::to_string(&snapshot)
+                .map_err(std::io::Error::from)
+                .and_then(|line| writeln!(file, "{}", line));
+            if let Err(e) = written {
+                if !snapshot_log_failed {
+                    tracing::warn!("Failed to write snapshot to log file: {}", e);
+                    snapshot_log_failed = true;
+                }
+            }
+        }
+
+        // Clear screen and move cursor to top, or fall back to scrolling if the terminal refuses
+        if redraw_in_place {
+            let mut out = stdout();
+            let cleared = out
+                .execute(cursor::MoveTo(0, 0))
+                .and_then(|out| out.execute(terminal::Clear(ClearType::FromCursorDown)))
+                .map(|_| ());
+            if let Err(e) = cleared {
+                tracing::warn!("Could not redraw the terminal ({}); printing each refresh below the last instead", e);
+                redraw_in_place = false;
+            }
+        }
+        if !redraw_in_place {
+            println!();
+        }
+
+        burn_rate.update(usage, cost);
+        print_live_frame(&frame, filter, &config, Some(&burn_rate));
+
+        println!("{}", display::divider(70).bright_blue());
+        if watcher.is_some() {
+            println!("  Watching for log changes... (Ctrl+C to exit)");
+        } else {
+            println!("  Refreshing every {} seconds... (Ctrl+C to exit)", refresh_seconds);
+        }
+        if let Some(duration) = duration {
+            let remaining = duration.saturating_sub(started.elapsed());
+            println!("  Stopping in {} seconds", remaining.as_secs());
+        }
+        println!("{}", display::divider(70).bright_blue());
+
+        // Wait before next update, never past the end of --duration, stopping cleanly on Ctrl+C.
+        // When watching, wait in debounce-sized steps until a log changes.
+        let remaining = || duration.map(|duration| duration.saturating_sub(started.elapsed()));
+        match &watcher {
+            None => {
+                let wait = remaining().map_or(refresh, |remaining| refresh.min(remaining));
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = &mut ctrl_c => break (frame.usage, cost),
+                }
+            }
+            Some(watcher) => loop {
+                let wait = remaining().map_or(local::WATCH_DEBOUNCE, |remaining| local::WATCH_DEBOUNCE.min(remaining));
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = &mut ctrl_c => break 'monitor (frame.usage, cost),
+                }
+                if watcher.changed() || remaining().is_some_and(|remaining| remaining.is_zero()) {
+                    break;
+                }
+            },
+        }
+        last_frame = (frame.usage, cost);
+    };
+
+    // Restore the cursor and leave the last frame in place rather than clearing it
+    stdout().execute(cursor::Show)?;
+    println!();
+    print_final_summary(&last_usage, last_cost, &model_pricing);
+
+    Ok(())
+}
+
+/// Usage parsed for one refresh of the live monitor, with any requested breakdowns
+struct LiveFrame {
+    usage: local::AggregatedUsage,
+    cost: f64,
+    months: Option<local::MonthlyUsage>,
+    agents: Option<local::AgentUsage>,
+    models: Option<local::ModelUsage>,
+    /// Project directories skipped by `--exclude`
+    excluded: usize,
+    /// Session being counted with `--session current`, once the logs name one
+    session: Option<String>,
+    /// Breakdowns that don't add up to the total, with `--debug`
+    discrepancies: Vec<String>,
+}
+
+/// Usage trackers kept across live refreshes, so each refresh only reads new log lines
+struct LiveTrackers {
+    projects_dir: PathBuf,
+    filter: local::UsageFilter,
+    /// Overall totals, only needed when no breakdown already covers them
+    total: Option<local::UsageTracker>,
+    months: Option<local::UsageTracker>,
+    agents: Option<local::UsageTracker>,
+    models: Option<local::UsageTracker>,
+    debug: bool,
+}
+
+impl LiveTrackers {
+    fn new(projects_dir: &Path, options: &LiveOptions) -> Self {
+        let keyed = |enabled: bool, key: fn(&local::LogEntry) -> String| {
+            enabled.then(|| local::UsageTracker::new(projects_dir, &options.filter).with_key(key))
+        };
+        let months = keyed(options.monthly, local::LogEntry::month_key);
+        let agents = keyed(options.by_agent, local::LogEntry::agent_key);
+        let models = keyed(options.by_model, local::LogEntry::model_key);
+        let total = (months.is_none() && agents.is_none() && models.is_none())
+            .then(|| local::UsageTracker::new(projects_dir, &options.filter));
+
+        Self {
+            projects_dir: projects_dir.to_path_buf(),
+            filter: options.filter.clone(),
+            total,
+            months,
+            agents,
+            models,
+            debug: options.debug,
+        }
+    }
+}
+
+/// Read new log lines, bucketed by month, agent, or model when requested
+fn collect_live_frame(trackers: &mut LiveTrackers, config: &config::Config) -> Result<LiveFrame> {
+    let months = refresh_buckets(&mut trackers.months)?;
+    let agents = refresh_buckets(&mut trackers.agents)?;
+    let models = refresh_buckets(&mut trackers.models)?;
+    let usage = match (&months, &agents, &models, &mut trackers.total) {
+        (Some(months), _, _, _) => months.values().sum(),
+        (None, Some(agents), _, _) => agents.values().sum(),
+        (None, None, Some(models), _) => models.values().sum(),
+        (None, None, None, Some(total)) => {
+            total.refresh()?;
+            total.total()
+        }
+        (None, None, None, None) => unreachable!("LiveTrackers always tracks totals without a breakdown"),
+    };
+
+    // Estimated cost: each model at its own rates when broken down by model,
+    // otherwise Claude Sonnet 4.5 pricing unless overridden
+    let cost = match &models {
+        Some(models) => models
+            .iter()
+            .map(|(model, usage)| calculate_cost(usage, &config.pricing_for(model)))
+            .sum(),
+        None => calculate_cost(&usage, &config.pricing_for(pricing::DEFAULT_MODEL)),
+    };
+
+    // Recounted every refresh so newly created projects that match are reported too
+    let excluded = local::excluded_projects(&trackers.projects_dir, &trackers.filter)?.len();
 
+    // Every tracker follows the same newest session
+    let session = [&trackers.total, &trackers.months, &trackers.agents, &trackers.models]
+        .into_iter()
+        .flatten()
+        .find_map(|tracker| tracker.session().map(str::to_string));
+
+    let discrepancies = if trackers.debug { check_breakdowns(trackers, &usage, [&months, &agents, &models]) } else { Vec::new() };
+
+    Ok(LiveFrame { usage, cost, months, agents, models, excluded, session, discrepancies })
+}
+
+/// Compare every breakdown of this refresh against `usage`, describing any that don't add up
+fn check_breakdowns(
+    trackers: &LiveTrackers,
+    usage: &local::AggregatedUsage,
+    breakdowns: [&Option<HashMap<String, local::AggregatedUsage>>; 3],
+) -> Vec<String> {
+    let tracker = [&trackers.total, &trackers.months, &trackers.agents, &trackers.models]
+        .into_iter()
+        .flatten()
+        .next()
+        .expect("LiveTrackers always has at least one tracker");
+    let by_project = Some(tracker.by_project());
+
+    ["month", "agent", "model"]
+        .into_iter()
+        .zip(breakdowns)
+        .chain([("project", &by_project)])
+        .filter_map(|(name, buckets)| {
+            let parts: Vec<local::AggregatedUsage> = buckets.as_ref()?.values().cloned().collect();
+            let error = usage.assert_consistent(&parts).err()?;
+            Some(format!("Per-{} breakdown: {}", name, error))
+        })
+        .collect()
+}
+
+/// Refresh an optional keyed tracker and return its buckets
+fn refresh_buckets(tracker: &mut Option<local::UsageTracker>) -> Result<Option<HashMap<String, local::AggregatedUsage>>> {
+    match tracker {
+        Some(tracker) => {
+            tracker.refresh()?;
+            Ok(Some(tracker.buckets()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Print one frame of the plain live view; the burn rate line is skipped when `burn_rate` is None
+fn print_live_frame(
+    frame: &LiveFrame,
+    filter: &local::UsageFilter,
+    config: &config::Config,
+    burn_rate: Option<&local::BurnRate>,
+) {
+    use colored::*;
+
+    let LiveFrame { usage, cost, months, agents, models, excluded, session, discrepancies } = frame;
+    let cost = *cost;
+    let model_pricing = config.pricing_for(pricing::DEFAULT_MODEL);
+
+    // Display header
+    println!("{}", display::divider(70).bright_blue());
+    println!("{}", "  CLAUDE CODE - LIVE TOKEN USAGE".bright_cyan().bold());
+    if let Some(model) = &filter.model {
+        println!("  {} {}", "Model filter:".cyan(), model.bright_white());
+    }
+    if let Some(project) = &filter.project {
+        println!("  {} {}", "Project:".cyan(), project.bright_white());
+    }
+    if filter.exclude.is_some() {
+        println!("  {} {}", "Excluded:".cyan(), format!("{} project(s) matching --exclude", excluded).bright_white());
+    }
+    if filter.current_session {
+        match session {
+            Some(session) => println!("  {} {}", "Session:".cyan(), session.bright_white()),
+            None => println!("  {} {}", "Session:".cyan(), "no session IDs in these logs; showing all-time usage".yellow()),
+        }
+    }
+    println!("{}", display::divider(70).bright_blue());
+    println!();
+
+    for discrepancy in discrepancies {
+        println!("{} {}\n", "Warning:".yellow().bold(), discrepancy.yellow());
+    }
+
+    if filter.is_active() && usage.message_count == 0 {
+        println!("  {}", "No matching usage for the active filter.".yellow());
+        println!();
+    } else {
         // Token counts
         println!("{}", "Token Usage:".bright_white().bold());
         println!("  {} {}", "Input tokens:       ".cyan(), format_number(usage.total_input).bright_white());
         println!("  {} {}", "Output tokens:      ".cyan(), format_number(usage.total_output).bright_white());
         println!("  {} {}", "Cache create tokens:".cyan(), format_number(usage.total_cache_creation).bright_white());
+        if usage.total_cache_creation_1h > 0 {
+            println!("  {} {}", "  of which 1-hour:  ".cyan(), format_number(usage.total_cache_creation_1h).bright_white());
+        }
         println!("  {} {}", "Cache read tokens:  ".cyan(), format_number(usage.total_cache_read).bright_white());
+        if usage.total_reasoning > 0 {
+            println!("  {} {}", "Reasoning tokens:   ".cyan(), format_number(usage.total_reasoning).bright_white());
+        }
         println!("  {} {}", "Total tokens:       ".cyan().bold(), format_number(usage.total()).bright_yellow().bold());
+        if display::shows_effective_tokens() {
+            let effective = display::format_effective_tokens(&model_pricing, usage.total_input, usage.total_cache_creation, usage.total_cache_read);
+            println!("  {} {}", "Effective input:    ".cyan(), effective);
+        }
         println!();
 
         // Stats
         println!("{}", "Statistics:".bright_white().bold());
         println!("  {} {}", "Messages processed: ".cyan(), usage.message_count.to_string().bright_white());
+        if let Some(burn_rate) = burn_rate {
+            println!("  {} {}", "Burn rate:          ".cyan(), burn_rate.to_string().bright_white());
+        }
         println!();
 
-        // Estimated cost (assuming Claude Sonnet 4.5 pricing)
-        let cost = calculate_cost(&usage);
         println!("{}", "Estimated Cost:".bright_white().bold());
-        println!("  {} {}", "Total cost:         ".cyan(), format!("${:.2}", cost).bright_green());
+        println!("  {} {}", "Total cost:         ".cyan(), display::format_cost(cost).bright_green());
+        println!("  {} {}", "Cache hit ratio:    ".cyan(), util::format_ratio(usage.cache_hit_ratio()).bright_white());
+        println!("  {} {}", "Cache savings:      ".cyan(), display::format_cost(usage.cache_savings(&model_pricing)).bright_green());
+        if usage.caching_looks_poor() {
+            println!(
+                "  {}",
+                format!(
+                    "Hint: only {:.2} cache-read tokens per uncached input token; consider prompt caching for repeated context",
+                    usage.cache_efficiency().unwrap_or(0.0)
+                )
+                .yellow()
+            );
+        }
         println!();
 
-        println!("{}", "═".repeat(70).bright_blue());
-        println!("  Refreshing every {} seconds... (Ctrl+C to exit)", refresh_seconds);
-        println!("{}", "═".repeat(70).bright_blue());
+        if let Some(months) = months {
+            print_monthly_rollup(months, &model_pricing);
+        }
 
-        // Wait before next update
-        tokio::time::sleep(Duration::from_secs(refresh_seconds)).await;
+        if let Some(agents) = agents {
+            print_agent_breakdown(agents, &model_pricing);
+        }
+
+        if let Some(models) = models {
+            print_model_breakdown(models, config, (usage.total(), cost));
+        }
     }
 }
 
-/// Format number with commas
-fn format_number(n: u64) -> String {
-    let s = n.to_string();
-    let mut result = String::new();
-    let mut count = 0;
+/// Print the totals shown when the live monitor exits
+/// `cost` is the one the last frame showed, so the two always agree
+fn print_final_summary(usage: &local::AggregatedUsage, cost: f64, model_pricing: &pricing::ModelPricing) {
+    use colored::*;
+
+    println!("{}", "Live monitor stopped.".bright_cyan().bold());
+    println!("  {} {}", "Final total tokens: ".cyan(), format_number(usage.total()).bright_yellow().bold());
+    if display::shows_effective_tokens() {
+        let effective = display::format_effective_tokens(model_pricing, usage.total_input, usage.total_cache_creation, usage.total_cache_read);
+        println!("  {} {}", "Effective input:    ".cyan(), effective);
+    }
+    println!("  {} {}", "Messages processed: ".cyan(), usage.message_count.to_string().bright_white());
+    println!("  {} {}", "Estimated cost:     ".cyan(), display::format_cost(cost).bright_green());
+}
+
+/// One line of the live monitor's `--log-file` output
+#[derive(serde::Serialize)]
+struct LiveSnapshot {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    total_input: u64,
+    total_output: u64,
+    total_cache_creation: u64,
+    total_cache_read: u64,
+    total_reasoning: u64,
+    total: u64,
+    message_count: usize,
+    /// Always USD, regardless of `--currency`
+    cost: f64,
+    /// Session counted with `--session current`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<String>,
+}
 
-    for c in s.chars().rev() {
-        if count == 3 {
-            result.insert(0, ',');
-            count = 0;
+impl LiveSnapshot {
+    fn new(usage: &local::AggregatedUsage, cost: f64, session: Option<String>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now(),
+            total_input: usage.total_input,
+            total_output: usage.total_output,
+            total_cache_creation: usage.total_cache_creation,
+            total_cache_read: usage.total_cache_read,
+            total_reasoning: usage.total_reasoning,
+            total: usage.total(),
+            message_count: usage.message_count,
+            cost,
+            session,
         }
-        result.insert(0, c);
-        count += 1;
     }
+}
+
+/// Print the current month prominently, followed by previous months (newest first)
+fn print_monthly_rollup(months: &local::MonthlyUsage, model_pricing: &pricing::ModelPricing) {
+    use colored::*;
+
+    let current_month = chrono::Local::now().format("%Y-%m").to_string();
+    let current = months.get(&current_month).cloned().unwrap_or_default();
+
+    println!("{}", format!("This Month ({}):", current_month).bright_white().bold());
+    println!("  {} {}", "Total tokens:       ".cyan().bold(), format_number(current.total()).bright_yellow().bold());
+    println!("  {} {}", "Messages processed: ".cyan(), current.message_count.to_string().bright_white());
+    println!("  {} {}", "Cost to date:       ".cyan(), display::format_cost(calculate_cost(&current, model_pricing)).bright_green().bold());
+    println!();
+
+    // Previous months sorted newest first, with the unknown bucket last
+    let mut previous: Vec<_> = months
+        .iter()
+        .filter(|(month, _)| **month != current_month)
+        .collect();
+    previous.sort_by(|(a, _), (b, _)| {
+        let a_unknown = *a == local::UNKNOWN_MONTH;
+        let b_unknown = *b == local::UNKNOWN_MONTH;
+        a_unknown.cmp(&b_unknown).then_with(|| b.cmp(a))
+    });
+
+    if previous.is_empty() {
+        return;
+    }
+
+    println!("{}", "Previous Months:".bright_white().bold());
+    for (month, usage) in previous {
+        println!("  {:<10} {:>18} {:>12}",
+            month.cyan(),
+            format_number(usage.total()).bright_white(),
+            display::format_cost(calculate_cost(usage, model_pricing)).bright_green()
+        );
+    }
+    println!();
+}
+
+/// Print a per-agent table sorted by total tokens, highest first
+fn print_agent_breakdown(agents: &local::AgentUsage, model_pricing: &pricing::ModelPricing) {
+    use colored::*;
+
+    let mut sorted: Vec<_> = agents.iter().collect();
+    sorted.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.total()));
+
+    println!("{}", "By Agent:".bright_white().bold());
+    println!("  {:<20} {:>10} {:>18} {:>12}",
+        "Agent".cyan().bold(),
+        "Messages".cyan().bold(),
+        "Tokens".cyan().bold(),
+        "Cost".cyan().bold()
+    );
+    for (agent, usage) in sorted {
+        println!("  {:<20} {:>10} {:>18} {:>12}",
+            agent.bright_white(),
+            usage.message_count.to_string().white(),
+            format_number(usage.total()).bright_white(),
+            display::format_cost(calculate_cost(usage, model_pricing)).bright_green()
+        );
+    }
+    println!();
+}
+
+/// Print a per-model table sorted by cost, highest first, colored by model family
+/// A footer reconciles the per-model sums against the grand `totals` (tokens, cost)
+fn print_model_breakdown(models: &local::ModelUsage, config: &config::Config, totals: (u64, f64)) {
+    use colored::*;
+
+    let mut rows: Vec<_> = models
+        .iter()
+        .map(|(model, usage)| (model, usage, calculate_cost(usage, &config.pricing_for(model))))
+        .collect();
+    rows.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    println!("{}", "By Model:".bright_white().bold());
+    println!("  {:<32} {:>14} {:>14} {:>16} {:>10}",
+        "Model".cyan().bold(),
+        "Input".cyan().bold(),
+        "Output".cyan().bold(),
+        "Total".cyan().bold(),
+        "Cost".cyan().bold()
+    );
+    for (model, usage, cost) in &rows {
+        let name = match pricing::model_family(model) {
+            pricing::ModelFamily::Opus => model.magenta(),
+            pricing::ModelFamily::Sonnet => model.bright_blue(),
+            pricing::ModelFamily::Haiku => model.green(),
+            pricing::ModelFamily::Other => model.white(),
+        };
+        println!("  {:<32} {:>14} {:>14} {:>16} {:>10}",
+            name,
+            format_number(usage.total_input).white(),
+            format_number(usage.total_output).white(),
+            format_number(usage.total()).bright_white(),
+            display::format_cost(*cost).bright_green()
+        );
+    }
+
+    // Footer: the per-model rows should add up exactly to the totals shown above
+    let (total_tokens, total_cost) = totals;
+    let sum_tokens: u64 = rows.iter().map(|(_, usage, _)| usage.total()).sum();
+    let sum_cost: f64 = rows.iter().map(|(_, _, cost)| cost).sum();
+    let reconciled = sum_tokens == total_tokens && (sum_cost - total_cost).abs() < 0.005;
+    println!("  {}", display::separator(90).bright_black());
+    println!("  {:<32} {:>14} {:>14} {:>16} {:>10}  {}",
+        format!("Sum of {} models", rows.len()).bright_white(),
+        "",
+        "",
+        format_number(sum_tokens).bright_yellow(),
+        display::format_cost(sum_cost).bright_green(),
+        if reconciled { "✓ matches total".green() } else { "✗ differs from total".red() }
+    );
+    println!();
+}
+
+/// Longest accepted `--refresh` interval, in seconds (one day)
+const MAX_REFRESH_SECONDS: f64 = 86_400.0;
+
+/// Parse a `--refresh` interval: positive, finite, and at most `MAX_REFRESH_SECONDS`
+/// The configurable lower bound is checked once the config is loaded
+fn parse_refresh(s: &str) -> Result<f64, String> {
+    let seconds: f64 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of seconds", s))?;
+
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err("refresh interval must be greater than 0 seconds".to_string());
+    }
+    if seconds > MAX_REFRESH_SECONDS {
+        return Err(format!("refresh interval must be at most {} seconds (one day)", MAX_REFRESH_SECONDS));
+    }
+
+    Ok(seconds)
+}
+
+/// Parse a `--warn` threshold: a finite percentage from 0 to 100
+fn parse_warn(s: &str) -> Result<f64, String> {
+    let percent: f64 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a percentage", s))?;
+
+    if !percent.is_finite() || !(0.0..=100.0).contains(&percent) {
+        return Err("warning threshold must be a percentage from 0 to 100".to_string());
+    }
+
+    Ok(percent)
+}
+
+/// Parse a `YYYY-MM` month into the date of its first day
+fn parse_month(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a month in YYYY-MM format", s))
+}
+
+/// First day of the month after `month_start`
+fn next_month(month_start: NaiveDate) -> NaiveDate {
+    month_start
+        .checked_add_months(chrono::Months::new(1))
+        .unwrap_or(NaiveDate::MAX)
+}
 
-    result
+/// Map budget usage to an exit code: 0 under warn, 2 between warn and limit, 1 at or over limit
+fn budget_exit_code(percentage: f64, warn: f64) -> i32 {
+    if percentage >= 100.0 {
+        1
+    } else if percentage >= warn {
+        2
+    } else {
+        0
+    }
 }
 
 /// Calculate estimated cost based on usage
-fn calculate_cost(usage: &local::AggregatedUsage) -> f64 {
-    // Claude Sonnet 4.5 pricing (approximation)
-    // Input: $3 per million tokens
-    // Output: $15 per million tokens
-    // Cache write: $3.75 per million tokens
-    // Cache read: $0.30 per million tokens
-
-    let input_cost = (usage.total_input as f64 / 1_000_000.0) * 3.0;
-    let output_cost = (usage.total_output as f64 / 1_000_000.0) * 15.0;
-    let cache_write_cost = (usage.total_cache_creation as f64 / 1_000_000.0) * 3.75;
-    let cache_read_cost = (usage.total_cache_read as f64 / 1_000_000.0) * 0.30;
-
-    input_cost + output_cost + cache_write_cost + cache_read_cost
+fn calculate_cost(usage: &local::AggregatedUsage, pricing: &pricing::ModelPricing) -> f64 {
+    usage.estimated_cost(pricing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_accepts_fractions_up_to_a_day() {
+        assert_eq!(parse_refresh("0.5"), Ok(0.5));
+        assert_eq!(parse_refresh("2"), Ok(2.0));
+        assert_eq!(parse_refresh("86400"), Ok(MAX_REFRESH_SECONDS));
+    }
+
+    #[test]
+    fn refresh_rejects_non_positive_non_finite_and_huge_values() {
+        for bad in ["0", "-1", "NaN", "inf", "-inf", "soon", "", "86400.5"] {
+            assert!(parse_refresh(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn months_parse_from_year_and_month_only() {
+        assert_eq!(parse_month("2024-02"), Ok(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()));
+        for bad in ["2024-13", "2024-00", "2024-1-x", "2024", "Feb 2024", "2024-02-15"] {
+            assert!(parse_month(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn next_month_rolls_december_into_the_next_year() {
+        let month = |y, m| NaiveDate::from_ymd_opt(y, m, 1).unwrap();
+        assert_eq!(next_month(month(2024, 1)), month(2024, 2));
+        assert_eq!(next_month(month(2024, 12)), month(2025, 1));
+    }
+
+    #[test]
+    fn updating_a_profile_never_overwrites_a_newer_config() {
+        let dir = std::env::temp_dir().join(format!("claude-token-counter-test-newer-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let newer = format!("version = {}\n\n[profiles.work]\napi_key = \"sk-ant-admin-keep\"\n", config::CONFIG_VERSION + 1);
+        std::fs::write(&path, &newer).unwrap();
+
+        let err = update_profile(Some(&path), None, Some("sk-ant-admin-new".to_string()), None, None).unwrap_err();
+        assert!(err.to_string().contains("newer version"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer);
+
+        // A config that doesn't exist yet is created from defaults
+        let fresh = dir.join("fresh.toml");
+        assert_eq!(update_profile(Some(&fresh), None, Some("sk-ant-admin-new".to_string()), Some(5), None).unwrap(), "default");
+        assert_eq!(config::Config::load(Some(&fresh)).unwrap().profile(None).unwrap().monthly_limit, Some(5));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn budget_exit_code_changes_at_warn_and_at_the_limit() {
+        assert_eq!(budget_exit_code(79.0, 80.0), 0);
+        assert_eq!(budget_exit_code(80.0, 80.0), 2);
+        assert_eq!(budget_exit_code(99.9, 80.0), 2);
+        assert_eq!(budget_exit_code(100.0, 80.0), 1);
+        assert_eq!(budget_exit_code(150.0, 80.0), 1);
+
+        // Thresholds that would make the codes meaningless are rejected up front
+        assert_eq!(parse_warn("0"), Ok(0.0));
+        assert_eq!(parse_warn("100"), Ok(100.0));
+        for bad in ["-1", "100.5", "NaN", "inf", "eighty", ""] {
+            assert!(parse_warn(bad).is_err(), "{:?} should be rejected", bad);
+        }
+        assert!(Cli::try_parse_from(["claude-token-counter", "budget", "--limit", "0"]).is_err());
+        assert!(Cli::try_parse_from(["claude-token-counter", "budget", "--limit", "1"]).is_ok());
+    }
 }
@@ -0,0 +1,108 @@
+use std::fmt::Write;
+
+use crate::local::AggregatedUsage;
+
+/// Usage and estimated cost for one combination of labels (e.g. a project and model)
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// Label names and values, in output order; empty for overall totals
+    pub labels: Vec<(&'static str, String)>,
+    pub usage: AggregatedUsage,
+    /// Estimated cost in USD
+    pub cost: f64,
+}
+
+/// One exported gauge family
+struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    value: fn(&Sample) -> f64,
+}
+
+const GAUGES: [Gauge; 5] = [
+    Gauge {
+        name: "claude_input_tokens_total",
+        help: "Uncached input tokens in Claude Code logs",
+        value: |s| s.usage.total_input as f64,
+    },
+    Gauge {
+        name: "claude_output_tokens_total",
+        help: "Output tokens in Claude Code logs",
+        value: |s| s.usage.total_output as f64,
+    },
+    Gauge {
+        name: "claude_cache_creation_tokens_total",
+        help: "Cache write tokens in Claude Code logs",
+        value: |s| s.usage.total_cache_creation as f64,
+    },
+    Gauge {
+        name: "claude_cache_read_tokens_total",
+        help: "Cache read tokens in Claude Code logs",
+        value: |s| s.usage.total_cache_read as f64,
+    },
+    Gauge {
+        name: "claude_estimated_cost_usd",
+        help: "Estimated cost in US dollars",
+        value: |s| s.cost,
+    },
+];
+
+/// Render samples in the Prometheus text exposition format, one gauge family per metric
+pub fn render(samples: &[Sample]) -> String {
+    let mut out = String::new();
+
+    for gauge in &GAUGES {
+        let _ = writeln!(out, "# HELP {} {}", gauge.name, gauge.help);
+        let _ = writeln!(out, "# TYPE {} gauge", gauge.name);
+        for sample in samples {
+            let _ = writeln!(out, "{}{} {}", gauge.name, format_labels(&sample.labels), (gauge.value)(sample));
+        }
+    }
+
+    out
+}
+
+/// `{name="value",...}`, or nothing when there are no labels
+fn format_labels(labels: &[(&str, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Escape backslashes, quotes, and newlines as the exposition format requires
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_labeled_gauges() {
+        let usage = AggregatedUsage { total_input: 100, total_output: 20, total_cache_read: 5, ..Default::default() };
+        let sample = Sample {
+            labels: vec![("project", "my \"repo\"".to_string()), ("model", "claude-opus-4-1".to_string())],
+            usage,
+            cost: 0.25,
+        };
+        let text = render(&[sample]);
+
+        assert!(text.contains("# TYPE claude_input_tokens_total gauge\n"));
+        assert!(text.contains("claude_input_tokens_total{project=\"my \\\"repo\\\"\",model=\"claude-opus-4-1\"} 100\n"));
+        assert!(text.contains("claude_cache_read_tokens_total{project=\"my \\\"repo\\\"\",model=\"claude-opus-4-1\"} 5\n"));
+        assert!(text.contains("claude_estimated_cost_usd{project=\"my \\\"repo\\\"\",model=\"claude-opus-4-1\"} 0.25\n"));
+    }
+
+    #[test]
+    fn unlabeled_sample_has_no_braces() {
+        let sample = Sample { labels: Vec::new(), usage: AggregatedUsage::default(), cost: 0.0 };
+        assert!(render(&[sample]).contains("\nclaude_output_tokens_total 0\n"));
+    }
+}
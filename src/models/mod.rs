@@ -1,10 +1,17 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Once;
 
-/// Response from the Anthropic Usage & Cost API
+use crate::local::AggregatedUsage;
+use crate::pricing::ModelPricing;
+
+/// One response from a paginated Anthropic report, with later pages appended to `data`
 #[derive(Debug, Deserialize, Serialize)]
-pub struct UsageResponse {
-    /// Usage data grouped by time buckets
-    pub data: Vec<UsageRecord>,
+pub struct ReportPage<T> {
+    /// Report rows grouped by time buckets
+    pub data: Vec<T>,
 
     /// Whether there are more pages
     #[serde(default)]
@@ -13,8 +20,18 @@ pub struct UsageResponse {
     /// Next page token for pagination
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_page: Option<String>,
+
+    /// Set when a later page failed and `data` holds only the pages fetched before it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
 }
 
+/// Response from the Anthropic Usage API
+pub type UsageResponse = ReportPage<UsageRecord>;
+
+/// Response from the Anthropic cost report endpoint
+pub type CostResponse = ReportPage<CostRecord>;
+
 /// Individual usage record for a time bucket
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UsageRecord {
@@ -29,6 +46,360 @@ pub struct UsageRecord {
     pub results: Vec<UsageDetail>,
 }
 
+impl CostResponse {
+    /// Billed total across every bucket, in USD
+    pub fn total_usd(&self) -> f64 {
+        self.data.iter().map(CostRecord::billed_usd).sum()
+    }
+}
+
+/// Billed costs for one time bucket
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CostRecord {
+    /// Start time of this bucket (ISO 8601)
+    pub starting_at: String,
+
+    /// End time of this bucket (ISO 8601)
+    pub ending_at: String,
+
+    /// One entry per cost line item (or per group, when grouped)
+    #[serde(default)]
+    pub results: Vec<CostDetail>,
+}
+
+impl CostRecord {
+    /// Billed total for this bucket, in USD
+    pub fn billed_usd(&self) -> f64 {
+        self.results.iter().map(CostDetail::usd).sum()
+    }
+
+    /// Bucket start date (YYYY-MM-DD), matching `UsageRecord::date`
+    pub fn date(&self) -> String {
+        DateTime::parse_from_rfc3339(&self.starting_at)
+            .map(|t| t.date_naive().format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| INVALID_DATE.to_string())
+    }
+}
+
+/// A single billed amount
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CostDetail {
+    /// Amount in the currency's lowest unit (cents for USD), as a decimal string
+    pub amount: String,
+
+    /// ISO 4217 code; the API currently reports only "USD"
+    #[serde(default = "default_cost_currency")]
+    pub currency: String,
+
+    /// Workspace the cost belongs to, when grouped by workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
+
+    /// Line item description, when grouped by description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+fn default_cost_currency() -> String {
+    "USD".to_string()
+}
+
+impl CostDetail {
+    /// This amount in dollars; an amount that doesn't parse counts as nothing
+    pub fn usd(&self) -> f64 {
+        self.amount.trim().parse::<f64>().map_or(0.0, |cents| cents / 100.0)
+    }
+}
+
+/// Time granularity used to bucket usage records for display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Grouping {
+    /// One row per day (the API's native bucket size)
+    #[default]
+    Day,
+    /// One row per ISO week (Monday to Sunday)
+    Week,
+    /// One row per calendar month
+    Month,
+}
+
+impl Grouping {
+    /// Column label for the bucket key
+    pub fn label(&self) -> &'static str {
+        match self {
+            Grouping::Day => "Date",
+            Grouping::Week => "Week",
+            Grouping::Month => "Month",
+        }
+    }
+
+    /// Bucket key for a YYYY-MM-DD date: the date itself, YYYY-Www, or YYYY-MM
+    /// Dates that don't parse are used as their own key
+    pub fn bucket_key(&self, date: &str) -> String {
+        let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d");
+        match (self, parsed) {
+            (Grouping::Week, Ok(d)) => {
+                let week = d.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            (Grouping::Month, Ok(d)) => format!("{}-{:02}", d.year(), d.month()),
+            _ => date.to_string(),
+        }
+    }
+
+    /// Re-bucket records by this grouping, summing token counts within each bucket
+    /// Each merged record spans its earliest `starting_at` to its latest `ending_at`
+    pub fn group_records(&self, records: &[UsageRecord]) -> Vec<UsageRecord> {
+        if *self == Grouping::Day {
+            return records.to_vec();
+        }
+
+        let mut buckets: BTreeMap<String, UsageRecord> = BTreeMap::new();
+        for record in records {
+            let key = self.bucket_key(&record.date());
+            match buckets.get_mut(&key) {
+                Some(bucket) => {
+                    if record.starting_at < bucket.starting_at {
+                        bucket.starting_at = record.starting_at.clone();
+                    }
+                    if record.ending_at > bucket.ending_at {
+                        bucket.ending_at = record.ending_at.clone();
+                    }
+                    bucket.results.extend(record.results.iter().cloned());
+                }
+                None => {
+                    buckets.insert(key, record.clone());
+                }
+            }
+        }
+
+        buckets.into_values().collect()
+    }
+}
+
+/// Length of the window covered by `report`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportPeriod {
+    /// The last 7 days, compared with the 7 before
+    #[default]
+    Week,
+    /// The last 30 days, compared with the 30 before
+    Month,
+}
+
+impl ReportPeriod {
+    /// Number of days in one period
+    pub fn days(&self) -> u32 {
+        match self {
+            ReportPeriod::Week => 7,
+            ReportPeriod::Month => 30,
+        }
+    }
+
+    /// Noun used in prose ("week" or "month")
+    pub fn noun(&self) -> &'static str {
+        match self {
+            ReportPeriod::Week => "week",
+            ReportPeriod::Month => "month",
+        }
+    }
+}
+
+/// Measure `top-projects` ranks by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RankBy {
+    /// Total tokens across all four token kinds
+    #[default]
+    Tokens,
+    /// Estimated cost, each model at its own rates
+    Cost,
+}
+
+/// Which sessions `live` counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SessionScope {
+    /// Only the most recent session (by the logs' sessionId)
+    Current,
+    /// Every session on record
+    #[default]
+    All,
+}
+
+/// One project's local usage, as ranked by `top-projects`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectTotal {
+    pub name: String,
+    pub tokens: u64,
+    pub cost: f64,
+    /// Tokens logged so far this calendar month
+    pub month_tokens: u64,
+    /// Monthly limit that `month_tokens` is measured against, if any
+    pub monthly_limit: Option<u64>,
+}
+
+impl ProjectTotal {
+    /// This project's value of the `by` measure
+    pub fn value(&self, by: RankBy) -> f64 {
+        match by {
+            RankBy::Tokens => self.tokens as f64,
+            RankBy::Cost => self.cost,
+        }
+    }
+}
+
+/// Sort projects largest first by `by`, breaking ties by name
+pub fn rank_projects(projects: &mut [ProjectTotal], by: RankBy) {
+    projects.sort_by(|a, b| b.value(by).total_cmp(&a.value(by)).then_with(|| a.name.cmp(&b.name)));
+}
+
+/// Split records into those starting on or after `boundary` and those before it
+/// Records with unparseable timestamps are dropped
+pub fn split_records_at(records: &[UsageRecord], boundary: DateTime<Utc>) -> (Vec<UsageRecord>, Vec<UsageRecord>) {
+    let mut current = Vec::new();
+    let mut previous = Vec::new();
+    for record in records {
+        match record.date_time() {
+            Some(start) if start >= boundary => current.push(record.clone()),
+            Some(_) => previous.push(record.clone()),
+            None => {}
+        }
+    }
+    (current, previous)
+}
+
+/// Key the history table is sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Bucket start time
+    #[default]
+    Date,
+    /// Total tokens in the bucket
+    Total,
+}
+
+/// Direction of a sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortOrder {
+    /// Smallest (or oldest) first
+    Asc,
+    /// Largest (or newest) first
+    #[default]
+    Desc,
+}
+
+/// How `history` renders its table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HistoryFormat {
+    /// Colored terminal table
+    #[default]
+    Table,
+    /// GitHub-flavored Markdown table, for pasting into issues and PRs
+    Markdown,
+    /// One JSON object per line, oldest first (the rows of --json), streamed as written
+    Jsonl,
+}
+
+/// Sort records in place by `key` and `order`
+/// Records with equal totals stay in date order (following `order` too), so ties are stable
+pub fn sort_records(records: &mut [UsageRecord], key: SortKey, order: SortOrder) {
+    let directed = |ordering: std::cmp::Ordering| match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    };
+
+    records.sort_by(|a, b| directed(a.starting_at.cmp(&b.starting_at)));
+    if key == SortKey::Total {
+        records.sort_by(|a, b| directed(a.total().cmp(&b.total())));
+    }
+}
+
+/// One bucket of `history --json` (or `--format jsonl`) output
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryRow {
+    pub date: String,
+    pub starting_at: String,
+    pub ending_at: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub total: u64,
+    /// Estimated cost of this bucket in USD
+    pub cost: f64,
+    /// Running total of `cost` from the oldest bucket through this one
+    pub cumulative_cost: f64,
+}
+
+/// One day of the `cost` comparison: what Anthropic billed against our estimate
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyCost {
+    pub date: String,
+    pub billed: f64,
+    pub estimated: f64,
+}
+
+/// Pair billed costs with estimated usage costs by day, oldest first
+/// Days that appear in only one report get zero for the other
+pub fn daily_costs(costs: &[CostRecord], usage: &[UsageRecord], pricing: &ModelPricing) -> Vec<DailyCost> {
+    let mut days: BTreeMap<String, DailyCost> = BTreeMap::new();
+    let entries = costs.iter().map(|record| (record.date(), record.billed_usd(), 0.0))
+        .chain(usage.iter().map(|record| (record.date(), 0.0, record.estimated_cost(pricing))));
+    for (date, billed, estimated) in entries {
+        let day = days.entry(date.clone())
+            .or_insert(DailyCost { date, billed: 0.0, estimated: 0.0 });
+        day.billed += billed;
+        day.estimated += estimated;
+    }
+    days.into_values().collect()
+}
+
+/// Build export rows oldest first, accumulating cost as they go
+pub fn history_rows(records: &[UsageRecord], pricing: &ModelPricing) -> Vec<HistoryRow> {
+    let mut records = records.to_vec();
+    history_row_iter(&mut records, pricing).collect()
+}
+
+/// Write export rows oldest first as JSON Lines, one row at a time instead of building them all
+/// `records` is sorted in place
+pub fn write_history_jsonl(records: &mut [UsageRecord], pricing: &ModelPricing, mut writer: impl Write) -> io::Result<()> {
+    for row in history_row_iter(records, pricing) {
+        serde_json::to_writer(&mut writer, &row)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Sort `records` oldest first and lazily turn each into an export row with a running cost
+fn history_row_iter<'a>(records: &'a mut [UsageRecord], pricing: &'a ModelPricing) -> impl Iterator<Item = HistoryRow> + 'a {
+    sort_records(records, SortKey::Date, SortOrder::Asc);
+
+    let mut cumulative_cost = 0.0;
+    records.iter().map(move |record| {
+        let cost = record.estimated_cost(pricing);
+        cumulative_cost += cost;
+        HistoryRow {
+            date: record.date(),
+            starting_at: record.starting_at.clone(),
+            ending_at: record.ending_at.clone(),
+            input_tokens: record.input_tokens(),
+            output_tokens: record.output_tokens(),
+            cache_creation_tokens: record.cache_creation_tokens(),
+            cache_read_tokens: record.cache_read_tokens(),
+            reasoning_tokens: record.reasoning_tokens(),
+            total: record.total(),
+            cost,
+            cumulative_cost,
+        }
+    })
+}
+
+/// Placeholder date shown for records whose `starting_at` can't be parsed
+pub const INVALID_DATE: &str = "????-??-??";
+
+/// Guards the malformed-timestamp warning so it prints once per run
+static WARN_INVALID_TIMESTAMP: Once = Once::new();
+
 /// Detailed usage information within a time bucket
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UsageDetail {
@@ -47,6 +418,10 @@ pub struct UsageDetail {
     /// Cache read tokens
     #[serde(default)]
     pub cache_read_input_tokens: u64,
+
+    /// Reasoning (thinking) tokens reported apart from output
+    #[serde(default)]
+    pub reasoning_tokens: u64,
 }
 
 impl UsageRecord {
@@ -55,6 +430,11 @@ impl UsageRecord {
         self.results.iter().map(|r| r.total()).sum()
     }
 
+    /// Whether the API returned this bucket with no results at all
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
     /// Get input tokens for this bucket
     pub fn input_tokens(&self) -> u64 {
         self.results.iter().map(|r| r.input_tokens).sum()
@@ -65,17 +445,65 @@ impl UsageRecord {
         self.results.iter().map(|r| r.output_tokens).sum()
     }
 
-    /// Get date string from starting_at
+    /// Get cache creation tokens for this bucket
+    pub fn cache_creation_tokens(&self) -> u64 {
+        self.results.iter().map(|r| r.cache_creation_input_tokens).sum()
+    }
+
+    /// Get cache read tokens for this bucket
+    pub fn cache_read_tokens(&self) -> u64 {
+        self.results.iter().map(|r| r.cache_read_input_tokens).sum()
+    }
+
+    /// Get reasoning tokens for this bucket
+    pub fn reasoning_tokens(&self) -> u64 {
+        self.results.iter().map(|r| r.reasoning_tokens).sum()
+    }
+
+    /// Estimate cost in dollars for this bucket, including cache and reasoning tokens
+    pub fn estimated_cost(&self, pricing: &ModelPricing) -> f64 {
+        crate::pricing::calculate_cost(
+            pricing,
+            self.input_tokens(),
+            self.output_tokens(),
+            self.cache_creation_tokens(),
+            self.cache_read_tokens(),
+        ) + crate::pricing::reasoning_cost(pricing, self.reasoning_tokens())
+    }
+
+    /// Bucket start as a timestamp, or None when `starting_at` isn't RFC 3339
+    pub fn date_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.starting_at)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Bucket start date (YYYY-MM-DD, in the timestamp's own offset)
+    /// Returns `INVALID_DATE` with a one-time warning when `starting_at` doesn't parse
     pub fn date(&self) -> String {
-        // Extract just the date part (YYYY-MM-DD) from ISO 8601 timestamp
-        self.starting_at.split('T').next().unwrap_or(&self.starting_at).to_string()
+        match DateTime::parse_from_rfc3339(&self.starting_at) {
+            Ok(t) => t.date_naive().format("%Y-%m-%d").to_string(),
+            Err(_) => {
+                WARN_INVALID_TIMESTAMP.call_once(|| {
+                    tracing::warn!(
+                        "API returned an unparseable bucket timestamp {:?}; showing it as {}",
+                        self.starting_at, INVALID_DATE
+                    );
+                });
+                INVALID_DATE.to_string()
+            }
+        }
     }
 }
 
 impl UsageDetail {
-    /// Calculate total tokens (input + output + cache)
+    /// Calculate total tokens (input + output + cache + reasoning)
     pub fn total(&self) -> u64 {
-        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+            + self.reasoning_tokens
     }
 }
 
@@ -84,26 +512,65 @@ impl UsageDetail {
 pub struct UsageSummary {
     pub total_input_tokens: u64,
     pub total_output_tokens: u64,
+    pub total_cache_creation_tokens: u64,
+    pub total_cache_read_tokens: u64,
+    pub total_reasoning_tokens: u64,
+    /// Headline total: input + output + reasoning, plus cache tokens when `includes_cache`
     pub total_tokens: u64,
+    /// Whether `total_tokens` (and so the quota figures) counts cache tokens
+    pub includes_cache: bool,
     pub days_with_usage: usize,
+    /// Number of time buckets the API returned (0 means the query came back empty)
+    pub record_count: usize,
 }
 
 impl UsageSummary {
     /// Create a summary from a list of usage records
+    /// The headline total is `io_tokens` until `with_cache_in_total(true)` makes it `all_tokens`
     pub fn from_records(records: &[UsageRecord]) -> Self {
         let total_input_tokens: u64 = records.iter().map(|r| r.input_tokens()).sum();
         let total_output_tokens: u64 = records.iter().map(|r| r.output_tokens()).sum();
-        let total_tokens = total_input_tokens + total_output_tokens;
+        let total_cache_creation_tokens: u64 = records.iter().map(|r| r.cache_creation_tokens()).sum();
+        let total_cache_read_tokens: u64 = records.iter().map(|r| r.cache_read_tokens()).sum();
+        let total_reasoning_tokens: u64 = records.iter().map(|r| r.reasoning_tokens()).sum();
+        let total_tokens = total_input_tokens + total_output_tokens + total_reasoning_tokens;
         let days_with_usage = records.iter().filter(|r| r.total() > 0).count();
 
         Self {
             total_input_tokens,
             total_output_tokens,
+            total_cache_creation_tokens,
+            total_cache_read_tokens,
+            total_reasoning_tokens,
             total_tokens,
+            includes_cache: false,
             days_with_usage,
+            record_count: records.len(),
         }
     }
 
+    /// Choose whether the headline total counts cache creation and read tokens
+    pub fn with_cache_in_total(mut self, include: bool) -> Self {
+        self.total_tokens = if include { self.all_tokens() } else { self.io_tokens() };
+        self.includes_cache = include;
+        self
+    }
+
+    /// Input, output, and reasoning tokens: the "I/O total"
+    pub fn io_tokens(&self) -> u64 {
+        self.total_input_tokens + self.total_output_tokens + self.total_reasoning_tokens
+    }
+
+    /// Cache creation and cache read tokens
+    pub fn cache_tokens(&self) -> u64 {
+        self.total_cache_creation_tokens + self.total_cache_read_tokens
+    }
+
+    /// Every token kind, matching the sum of `UsageRecord::total()` and the live view's total
+    pub fn all_tokens(&self) -> u64 {
+        self.io_tokens() + self.cache_tokens()
+    }
+
     /// Calculate percentage used of a given limit
     pub fn percentage_used(&self, limit: u64) -> f64 {
         if limit == 0 {
@@ -112,8 +579,487 @@ impl UsageSummary {
         (self.total_tokens as f64 / limit as f64) * 100.0
     }
 
+    /// Estimate cost in dollars, including cache and reasoning tokens
+    pub fn estimated_cost(&self, pricing: &ModelPricing) -> f64 {
+        crate::pricing::calculate_cost(
+            pricing,
+            self.total_input_tokens,
+            self.total_output_tokens,
+            self.total_cache_creation_tokens,
+            self.total_cache_read_tokens,
+        ) + crate::pricing::reasoning_cost(pricing, self.total_reasoning_tokens)
+    }
+
+    /// Share of prompt tokens served from cache, or None with no usage
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        crate::pricing::cache_hit_ratio(
+            self.total_input_tokens,
+            self.total_cache_creation_tokens,
+            self.total_cache_read_tokens,
+        )
+    }
+
+    /// Dollars saved by cache reads versus uncached input
+    pub fn cache_savings(&self, pricing: &ModelPricing) -> f64 {
+        crate::pricing::cache_savings(pricing, self.total_cache_read_tokens)
+    }
+
     /// Calculate remaining tokens from a limit
     pub fn remaining(&self, limit: u64) -> i64 {
         limit as i64 - self.total_tokens as i64
     }
+
+    /// Average tokens per day that had any usage, or None with no usage
+    pub fn daily_average(&self) -> Option<f64> {
+        (self.days_with_usage > 0).then(|| self.total_tokens as f64 / self.days_with_usage as f64)
+    }
+
+    /// Linear projection of a month's total at the current daily average
+    pub fn projected_month_total(&self, days_in_month: u32) -> Option<u64> {
+        self.daily_average().map(|average| (average * f64::from(days_in_month)).round() as u64)
+    }
+}
+
+/// Completed days of history `forecast_month` needs: two of each weekday
+pub const MIN_FORECAST_DAYS: usize = 14;
+
+/// Month-end usage expected from each weekday's average, with a rough 95% range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Forecast {
+    /// Tokens used so far this month, today included
+    pub month_to_date: u64,
+    pub projected: u64,
+    pub low: u64,
+    pub high: u64,
+}
+
+/// What `status --forecast` could work out from the fetched history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MonthForecast {
+    Trend(Forecast),
+    /// Fewer than `MIN_FORECAST_DAYS` completed days; the simple projection stands alone
+    TooLittleHistory { days: usize },
+}
+
+/// Forecast this month's total (UTC) from weekday patterns in `records`
+///
+/// Each remaining day is expected to see its weekday's average over the completed
+/// days in `records` (today counts for whatever its average exceeds usage so far).
+/// The range comes from how far days strayed from their weekday's average.
+pub fn forecast_month(records: &[UsageRecord], today: NaiveDate, include_cache: bool) -> MonthForecast {
+    let mut daily: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+    for record in records {
+        if let Some(start) = record.date_time() {
+            let tokens = if include_cache {
+                record.total()
+            } else {
+                record.total() - record.cache_creation_tokens() - record.cache_read_tokens()
+            };
+            *daily.entry(start.date_naive()).or_default() += tokens;
+        }
+    }
+
+    // Completed days from the first bucket through yesterday; days without a bucket used nothing
+    let history: Vec<(NaiveDate, u64)> = match daily.keys().next() {
+        Some(&first) if first < today => first
+            .iter_days()
+            .take_while(|day| *day < today)
+            .map(|day| (day, daily.get(&day).copied().unwrap_or(0)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if history.len() < MIN_FORECAST_DAYS {
+        return MonthForecast::TooLittleHistory { days: history.len() };
+    }
+
+    let mut sums = [0.0; 7];
+    let mut counts = [0.0; 7];
+    for (day, tokens) in &history {
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        sums[weekday] += *tokens as f64;
+        counts[weekday] += 1.0;
+    }
+    let mean = |day: NaiveDate| {
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        sums[weekday] / counts[weekday]
+    };
+
+    // Spread of days around their weekday's average (7 averages were fitted)
+    let squared: f64 = history.iter().map(|(day, tokens)| (*tokens as f64 - mean(*day)).powi(2)).sum();
+    let std_dev = (squared / (history.len() - 7) as f64).sqrt();
+
+    let today_so_far = daily.get(&today).copied().unwrap_or(0);
+    let month_to_date: u64 = daily
+        .range(today.with_day(1).expect("day 1 exists in every month")..=today)
+        .map(|(_, tokens)| tokens)
+        .sum();
+
+    let days_left = days_in_month(today) - today.day();
+    let rest_of_month = today.iter_days().skip(1).take(days_left as usize);
+    let expected = (mean(today) - today_so_far as f64).max(0.0) + rest_of_month.map(mean).sum::<f64>();
+    let spread = 1.96 * std_dev * f64::from(days_left + 1).sqrt();
+
+    MonthForecast::Trend(Forecast {
+        month_to_date,
+        projected: month_to_date + expected.round() as u64,
+        low: month_to_date + (expected - spread).max(0.0).round() as u64,
+        high: month_to_date + (expected + spread).round() as u64,
+    })
+}
+
+/// Number of days in the month containing `date`
+pub fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).expect("day 1 exists in every month");
+    let next = first.checked_add_months(chrono::Months::new(1)).expect("date within chrono's range");
+    (next - first).num_days() as u32
+}
+
+/// Records whose bucket starts on `day` (UTC), e.g. today's usage for a daily limit
+pub fn records_on(records: &[UsageRecord], day: NaiveDate) -> Vec<UsageRecord> {
+    records
+        .iter()
+        .filter(|record| record.date_time().is_some_and(|start| start.date_naive() == day))
+        .cloned()
+        .collect()
+}
+
+/// Token totals from either source (API or local logs), normalized for side-by-side display
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceTotals {
+    pub input: u64,
+    pub output: u64,
+    pub cache_creation: u64,
+    pub cache_read: u64,
+    pub reasoning: u64,
+}
+
+impl SourceTotals {
+    /// Total tokens across all token kinds
+    pub fn total(&self) -> u64 {
+        self.input + self.output + self.cache_creation + self.cache_read + self.reasoning
+    }
+
+    /// Estimate cost in dollars, including cache and reasoning tokens
+    pub fn estimated_cost(&self, pricing: &ModelPricing) -> f64 {
+        crate::pricing::calculate_cost(pricing, self.input, self.output, self.cache_creation, self.cache_read)
+            + crate::pricing::reasoning_cost(pricing, self.reasoning)
+    }
+}
+
+impl From<&UsageSummary> for SourceTotals {
+    fn from(summary: &UsageSummary) -> Self {
+        Self {
+            input: summary.total_input_tokens,
+            output: summary.total_output_tokens,
+            cache_creation: summary.total_cache_creation_tokens,
+            cache_read: summary.total_cache_read_tokens,
+            reasoning: summary.total_reasoning_tokens,
+        }
+    }
+}
+
+impl From<&AggregatedUsage> for SourceTotals {
+    fn from(usage: &AggregatedUsage) -> Self {
+        Self {
+            input: usage.total_input,
+            output: usage.total_output,
+            cache_creation: usage.total_cache_creation,
+            cache_read: usage.total_cache_read,
+            reasoning: usage.total_reasoning,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, input: u64, output: u64) -> UsageRecord {
+        UsageRecord {
+            starting_at: format!("{}T00:00:00Z", date),
+            ending_at: format!("{}T23:59:59Z", date),
+            results: vec![UsageDetail {
+                input_tokens: input,
+                output_tokens: output,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
+                reasoning_tokens: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn records_on_keeps_only_buckets_starting_that_day() {
+        let mut hourly = record("2025-01-07", 5, 5);
+        hourly.starting_at = "2025-01-07T13:00:00Z".to_string();
+        let records = vec![record("2025-01-06", 100, 10), record("2025-01-07", 200, 20), hourly];
+
+        let day = NaiveDate::from_ymd_opt(2025, 1, 7).unwrap();
+        let today = UsageSummary::from_records(&records_on(&records, day));
+        assert_eq!(today.total_tokens, 230);
+        assert!(records_on(&records, NaiveDate::from_ymd_opt(2025, 1, 8).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn bucket_keys_use_iso_weeks_and_calendar_months() {
+        // 2024-12-30 is a Monday in ISO week 1 of 2025
+        assert_eq!(Grouping::Week.bucket_key("2024-12-30"), "2025-W01");
+        assert_eq!(Grouping::Week.bucket_key("2024-12-29"), "2024-W52");
+        assert_eq!(Grouping::Month.bucket_key("2024-12-30"), "2024-12");
+        assert_eq!(Grouping::Day.bucket_key("2024-12-30"), "2024-12-30");
+        assert_eq!(Grouping::Month.bucket_key("not-a-date"), "not-a-date");
+    }
+
+    #[test]
+    fn group_records_sums_tokens_within_each_bucket() {
+        let records = vec![
+            record("2025-01-06", 100, 10),
+            record("2025-01-07", 200, 20),
+            record("2025-01-13", 400, 40),
+        ];
+
+        let weekly = Grouping::Week.group_records(&records);
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].input_tokens(), 300);
+        assert_eq!(weekly[0].output_tokens(), 30);
+        assert_eq!(weekly[0].starting_at, "2025-01-06T00:00:00Z");
+        assert_eq!(weekly[0].ending_at, "2025-01-07T23:59:59Z");
+        assert_eq!(weekly[1].total(), 440);
+
+        let monthly = Grouping::Month.group_records(&records);
+        assert_eq!(monthly.len(), 1);
+        assert_eq!(monthly[0].total(), 770);
+
+        assert_eq!(Grouping::Day.group_records(&records).len(), 3);
+    }
+
+    #[test]
+    fn summary_of_no_records_is_all_zero() {
+        let summary = UsageSummary::from_records(&[]);
+
+        assert_eq!(summary.total_tokens, 0);
+        assert_eq!(summary.days_with_usage, 0);
+        assert_eq!(summary.record_count, 0);
+        assert_eq!(summary.cache_hit_ratio(), None);
+    }
+
+    #[test]
+    fn record_with_empty_results_counts_as_a_bucket_without_usage() {
+        let empty = UsageRecord {
+            starting_at: "2025-01-06T00:00:00Z".to_string(),
+            ending_at: "2025-01-07T00:00:00Z".to_string(),
+            results: Vec::new(),
+        };
+        assert_eq!(empty.total(), 0);
+
+        let summary = UsageSummary::from_records(&[empty]);
+        assert_eq!(summary.record_count, 1);
+        assert_eq!(summary.days_with_usage, 0);
+        assert_eq!(summary.total_tokens, 0);
+    }
+
+    #[test]
+    fn cache_counts_toward_the_total_only_when_asked() {
+        let mut cached = record("2025-01-06", 100, 50);
+        cached.results[0].cache_creation_input_tokens = 1_000;
+        cached.results[0].cache_read_input_tokens = 10_000;
+
+        let summary = UsageSummary::from_records(std::slice::from_ref(&cached));
+        assert!(!summary.includes_cache);
+        assert_eq!(summary.total_tokens, 150);
+
+        let summary = summary.with_cache_in_total(true);
+        assert!(summary.includes_cache);
+        assert_eq!(summary.total_tokens, cached.total());
+        assert_eq!(summary.remaining(20_000), 20_000 - 11_150);
+
+        assert_eq!(summary.with_cache_in_total(false).total_tokens, 150);
+    }
+
+    #[test]
+    fn io_and_all_token_totals_are_pinned() {
+        let mut cached = record("2025-01-06", 100, 50);
+        cached.results[0].cache_creation_input_tokens = 1_000;
+        cached.results[0].cache_read_input_tokens = 10_000;
+        cached.results[0].reasoning_tokens = 5;
+        let records = vec![cached, record("2025-01-07", 1, 2)];
+
+        let summary = UsageSummary::from_records(&records);
+        assert_eq!(summary.io_tokens(), 100 + 50 + 5 + 1 + 2);
+        assert_eq!(summary.cache_tokens(), 11_000);
+        assert_eq!(summary.all_tokens(), records.iter().map(|r| r.total()).sum::<u64>());
+        assert_eq!(summary.total_tokens, summary.io_tokens());
+    }
+
+    #[test]
+    fn zero_limit_reports_zero_percent() {
+        let summary = UsageSummary::from_records(&[record("2025-01-06", 100, 50)]);
+
+        assert_eq!(summary.percentage_used(0), 0.0);
+    }
+
+    #[test]
+    fn overage_goes_past_100_percent_with_negative_remaining() {
+        let summary = UsageSummary::from_records(&[record("2025-01-06", 1_000, 500)]);
+
+        assert_eq!(summary.percentage_used(1_000), 150.0);
+        assert_eq!(summary.remaining(1_000), -500);
+        assert_eq!(summary.remaining(2_000), 500);
+    }
+
+    #[test]
+    fn days_with_usage_skips_zero_total_buckets() {
+        let records = vec![
+            record("2025-01-06", 100, 10),
+            record("2025-01-07", 0, 0),
+            record("2025-01-08", 0, 5),
+        ];
+        let summary = UsageSummary::from_records(&records);
+
+        assert_eq!(summary.record_count, 3);
+        assert_eq!(summary.days_with_usage, 2);
+    }
+
+    #[test]
+    fn projects_daily_average_over_the_month() {
+        let records = vec![record("2025-02-03", 900, 100), record("2025-02-04", 0, 0), record("2025-02-05", 2_000, 0)];
+        let summary = UsageSummary::from_records(&records);
+
+        assert_eq!(summary.daily_average(), Some(1_500.0));
+        assert_eq!(summary.projected_month_total(28), Some(42_000));
+        assert_eq!(UsageSummary::from_records(&[]).projected_month_total(31), None);
+    }
+
+    #[test]
+    fn forecast_follows_the_weekday_pattern() {
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // Four weeks from Monday 2025-01-06: 1,000 tokens on weekdays, nothing at weekends
+        let records: Vec<_> = date("2025-01-06")
+            .iter_days()
+            .take(28)
+            .map(|day| {
+                let tokens = if day.weekday().num_days_from_monday() < 5 { 1_000 } else { 0 };
+                record(&day.format("%Y-%m-%d").to_string(), tokens, 0)
+            })
+            .collect();
+
+        // Monday 2025-02-03 with nothing yet: 20 weekdays left in February, 0 so far
+        let MonthForecast::Trend(forecast) = forecast_month(&records, date("2025-02-03"), false) else {
+            panic!("four weeks of history should be enough");
+        };
+        assert_eq!(forecast.month_to_date, 0);
+        assert_eq!(forecast.projected, 20_000);
+        assert_eq!((forecast.low, forecast.high), (20_000, 20_000));
+
+        // Ten completed days can't show a weekly pattern yet
+        assert_eq!(forecast_month(&records[..10], date("2025-01-16"), false), MonthForecast::TooLittleHistory { days: 10 });
+    }
+
+    #[test]
+    fn days_in_month_handles_leap_years_and_december() {
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+
+        assert_eq!(days_in_month(date("2024-02-10")), 29);
+        assert_eq!(days_in_month(date("2025-02-28")), 28);
+        assert_eq!(days_in_month(date("2025-12-31")), 31);
+        assert_eq!(days_in_month(date("2025-04-01")), 30);
+    }
+
+    #[test]
+    fn sorts_by_total_with_ties_in_date_order() {
+        let mut records = vec![
+            record("2025-01-06", 10, 0),
+            record("2025-01-07", 50, 0),
+            record("2025-01-08", 10, 0),
+        ];
+        let dates = |records: &[UsageRecord]| records.iter().map(|r| r.date()).collect::<Vec<_>>();
+
+        sort_records(&mut records, SortKey::Date, SortOrder::Desc);
+        assert_eq!(dates(&records), ["2025-01-08", "2025-01-07", "2025-01-06"]);
+
+        sort_records(&mut records, SortKey::Total, SortOrder::Desc);
+        assert_eq!(dates(&records), ["2025-01-07", "2025-01-08", "2025-01-06"]);
+
+        sort_records(&mut records, SortKey::Total, SortOrder::Asc);
+        assert_eq!(dates(&records), ["2025-01-06", "2025-01-08", "2025-01-07"]);
+    }
+
+    #[test]
+    fn ranks_projects_by_tokens_or_cost() {
+        let project = |name: &str, tokens, cost| ProjectTotal { name: name.to_string(), tokens, cost, month_tokens: 0, monthly_limit: None };
+        let mut projects = vec![project("a", 100, 9.0), project("b", 500, 1.0), project("c", 100, 2.0)];
+        let names = |projects: &[ProjectTotal]| projects.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+
+        rank_projects(&mut projects, RankBy::Tokens);
+        assert_eq!(names(&projects), ["b", "a", "c"]);
+
+        rank_projects(&mut projects, RankBy::Cost);
+        assert_eq!(names(&projects), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn history_rows_accumulate_cost_oldest_first() {
+        let records = vec![
+            record("2025-01-08", 1_000_000, 0),
+            record("2025-01-06", 0, 1_000_000),
+            record("2025-01-07", 0, 0),
+        ];
+        let rows = history_rows(&records, &crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL));
+
+        let dates: Vec<_> = rows.iter().map(|row| row.date.as_str()).collect();
+        assert_eq!(dates, ["2025-01-06", "2025-01-07", "2025-01-08"]);
+        assert_eq!(rows.iter().map(|row| row.cost).collect::<Vec<_>>(), [15.0, 0.0, 3.0]);
+        assert_eq!(rows.iter().map(|row| row.cumulative_cost).collect::<Vec<_>>(), [15.0, 15.0, 18.0]);
+        assert!(rows.windows(2).all(|pair| pair[1].cumulative_cost >= pair[0].cumulative_cost));
+    }
+
+    #[test]
+    fn daily_costs_pair_billed_and_estimated_by_day() {
+        let costs: CostResponse = serde_json::from_str(r#"{
+            "data": [
+                {"starting_at": "2025-01-07T00:00:00Z", "ending_at": "2025-01-08T00:00:00Z",
+                 "results": [{"currency": "USD", "amount": "1250.5"}, {"amount": "49.5"}]},
+                {"starting_at": "2025-01-05T00:00:00Z", "ending_at": "2025-01-06T00:00:00Z", "results": []}
+            ],
+            "has_more": false
+        }"#).unwrap();
+        assert_eq!(costs.total_usd(), 13.0);
+
+        let usage = vec![record("2025-01-07", 1_000_000, 0), record("2025-01-06", 0, 1_000_000)];
+        let days = daily_costs(&costs.data, &usage, &crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL));
+        assert_eq!(days, [
+            DailyCost { date: "2025-01-05".to_string(), billed: 0.0, estimated: 0.0 },
+            DailyCost { date: "2025-01-06".to_string(), billed: 0.0, estimated: 15.0 },
+            DailyCost { date: "2025-01-07".to_string(), billed: 13.0, estimated: 3.0 },
+        ]);
+    }
+
+    #[test]
+    fn jsonl_export_writes_one_row_per_line() {
+        let mut records = vec![record("2025-01-07", 10, 0), record("2025-01-06", 0, 5)];
+        let mut out = Vec::new();
+        write_history_jsonl(&mut records, &crate::pricing::pricing_for(crate::pricing::DEFAULT_MODEL), &mut out).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["date"], "2025-01-06");
+        assert_eq!(lines[1]["total"], 10);
+    }
+
+    #[test]
+    fn malformed_timestamps_get_a_placeholder_date() {
+        let mut bad = record("2025-01-06", 1, 1);
+        bad.starting_at = "yesterday-ish".to_string();
+
+        assert_eq!(bad.date(), INVALID_DATE);
+        assert_eq!(bad.date_time(), None);
+
+        let good = record("2025-01-06", 1, 1);
+        assert_eq!(good.date(), "2025-01-06");
+        assert_eq!(good.date_time().unwrap().to_rfc3339(), "2025-01-06T00:00:00+00:00");
+    }
 }
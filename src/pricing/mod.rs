@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Model used for cost estimates when the model is unknown
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-5";
+
+/// Per-model token pricing in dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+    /// Reasoning (thinking) tokens; billed at the output rate when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<f64>,
+    /// One-hour cache writes (`cache_write` is the 5-minute rate); twice the input rate when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_1h: Option<f64>,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Self {
+        Self { input, output, cache_write, cache_read, reasoning: None, cache_write_1h: None }
+    }
+
+    /// Dollars per million reasoning tokens
+    pub fn reasoning_rate(&self) -> f64 {
+        self.reasoning.unwrap_or(self.output)
+    }
+
+    /// Dollars per million one-hour cache-write tokens
+    pub fn cache_write_1h_rate(&self) -> f64 {
+        self.cache_write_1h.unwrap_or(self.input * 2.0)
+    }
+
+    /// Prompt tokens as one input-equivalent figure: cache reads are scaled down by
+    /// their discount (`cache_read / input`), input and cache writes count in full
+    pub fn effective_input_tokens(&self, input: u64, cache_creation: u64, cache_read: u64) -> f64 {
+        let read_ratio = if self.input > 0.0 { self.cache_read / self.input } else { 1.0 };
+        (input + cache_creation) as f64 + cache_read as f64 * read_ratio
+    }
+}
+
+const OPUS_4_5: ModelPricing = ModelPricing::new(5.0, 25.0, 6.25, 0.50);
+const OPUS: ModelPricing = ModelPricing::new(15.0, 75.0, 18.75, 1.50);
+const SONNET: ModelPricing = ModelPricing::new(3.0, 15.0, 3.75, 0.30);
+const HAIKU_4_5: ModelPricing = ModelPricing::new(1.0, 5.0, 1.25, 0.10);
+const HAIKU_3_5: ModelPricing = ModelPricing::new(0.80, 4.0, 1.0, 0.08);
+const HAIKU_3: ModelPricing = ModelPricing::new(0.25, 1.25, 0.30, 0.03);
+
+/// Anthropic service tier a request was billed under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceTier {
+    /// Regular on-demand requests
+    #[default]
+    Standard,
+    /// Message Batches API requests, billed at half price
+    Batch,
+    /// Priority Tier requests, billed at list price against a capacity commitment
+    Priority,
+}
+
+impl ServiceTier {
+    /// Parse a `service_tier` value from a log or API response
+    /// Missing or unrecognized tiers are treated as standard
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(str::to_lowercase).as_deref() {
+            Some("batch") => ServiceTier::Batch,
+            Some("priority") => ServiceTier::Priority,
+            _ => ServiceTier::Standard,
+        }
+    }
+
+    /// Multiplier applied to list prices for this tier
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            ServiceTier::Standard | ServiceTier::Priority => 1.0,
+            ServiceTier::Batch => 0.5,
+        }
+    }
+}
+
+/// Look up the built-in pricing for a model name
+///
+/// Accepts both dated IDs (`claude-sonnet-4-5-20250929`) and dotted
+/// versions (`claude-sonnet-4.5`). Unknown models fall back to Sonnet pricing.
+pub fn pricing_for(model: &str) -> ModelPricing {
+    let model = model.to_lowercase().replace('.', "-");
+
+    if model.contains("opus-4-5") {
+        OPUS_4_5
+    } else if model.contains("opus") {
+        OPUS
+    } else if model.contains("haiku-4-5") {
+        HAIKU_4_5
+    } else if model.contains("3-5-haiku") || model.contains("haiku-3-5") {
+        HAIKU_3_5
+    } else if model.contains("haiku") {
+        HAIKU_3
+    } else {
+        SONNET
+    }
+}
+
+/// Broad model family, used to color-code per-model breakdowns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Opus,
+    Sonnet,
+    Haiku,
+    /// Anything that isn't a recognized Claude family
+    Other,
+}
+
+/// Classify a model name into its family
+pub fn model_family(model: &str) -> ModelFamily {
+    let model = model.to_lowercase();
+
+    if model.contains("opus") {
+        ModelFamily::Opus
+    } else if model.contains("sonnet") {
+        ModelFamily::Sonnet
+    } else if model.contains("haiku") {
+        ModelFamily::Haiku
+    } else {
+        ModelFamily::Other
+    }
+}
+
+/// Resolve pricing for a model, preferring user-configured overrides
+///
+/// Overrides are keyed by exact model name; unlisted models fall back to
+/// the built-in `pricing_for` table.
+pub fn resolve_pricing(
+    model: &str,
+    overrides: Option<&HashMap<String, ModelPricing>>,
+) -> ModelPricing {
+    overrides
+        .and_then(|map| map.get(model))
+        .copied()
+        .unwrap_or_else(|| pricing_for(model))
+}
+
+/// Calculate estimated cost in dollars for the given token counts
+pub fn calculate_cost(
+    pricing: &ModelPricing,
+    input: u64,
+    output: u64,
+    cache_creation: u64,
+    cache_read: u64,
+) -> f64 {
+    let input_cost = (input as f64 / 1_000_000.0) * pricing.input;
+    let output_cost = (output as f64 / 1_000_000.0) * pricing.output;
+    let cache_write_cost = (cache_creation as f64 / 1_000_000.0) * pricing.cache_write;
+    let cache_read_cost = (cache_read as f64 / 1_000_000.0) * pricing.cache_read;
+
+    input_cost + output_cost + cache_write_cost + cache_read_cost
+}
+
+/// Calculate estimated cost in dollars for reasoning tokens reported apart from output
+pub fn reasoning_cost(pricing: &ModelPricing, reasoning: u64) -> f64 {
+    (reasoning as f64 / 1_000_000.0) * pricing.reasoning_rate()
+}
+
+/// Calculate estimated cost in dollars for cache writes split into 5-minute and 1-hour buckets
+pub fn cache_write_cost(pricing: &ModelPricing, write_5m: u64, write_1h: u64) -> f64 {
+    (write_5m as f64 / 1_000_000.0) * pricing.cache_write + (write_1h as f64 / 1_000_000.0) * pricing.cache_write_1h_rate()
+}
+
+/// Calculate estimated cost in dollars for the given token counts billed under `tier`
+pub fn calculate_tiered_cost(
+    pricing: &ModelPricing,
+    tier: ServiceTier,
+    input: u64,
+    output: u64,
+    cache_creation: u64,
+    cache_read: u64,
+) -> f64 {
+    calculate_cost(pricing, input, output, cache_creation, cache_read) * tier.multiplier()
+}
+
+/// Share of prompt tokens served from cache: `cache_read / (input + cache_creation + cache_read)`
+/// Returns None when there were no prompt tokens at all
+pub fn cache_hit_ratio(input: u64, cache_creation: u64, cache_read: u64) -> Option<f64> {
+    let prompt_tokens = input + cache_creation + cache_read;
+    if prompt_tokens == 0 {
+        return None;
+    }
+    Some(cache_read as f64 / prompt_tokens as f64)
+}
+
+/// Dollars saved by reading `cache_read` tokens from cache instead of paying full input price
+pub fn cache_savings(pricing: &ModelPricing, cache_read: u64) -> f64 {
+    (cache_read as f64 / 1_000_000.0) * (pricing.input - pricing.cache_read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn sonnet_cost_for_sample_usage() {
+        let pricing = pricing_for("claude-sonnet-4-5-20250929");
+        // 1M input ($3) + 1M output ($15) + 1M cache write ($3.75) + 1M cache read ($0.30)
+        let cost = calculate_cost(&pricing, 1_000_000, 1_000_000, 1_000_000, 1_000_000);
+        assert_close(cost, 22.05);
+    }
+
+    #[test]
+    fn cache_reads_count_at_their_discount_in_effective_input() {
+        // Sonnet reads cost a tenth of input
+        let pricing = pricing_for("claude-sonnet-4-5");
+        assert_close(pricing.effective_input_tokens(1_000, 500, 10_000), 2_500.0);
+
+        let free_input = ModelPricing::new(0.0, 1.0, 0.0, 0.0);
+        assert_close(free_input.effective_input_tokens(1, 2, 3), 6.0);
+    }
+
+    #[test]
+    fn opus_cost_for_sample_usage() {
+        let pricing = pricing_for("claude-opus-4-1-20250805");
+        let cost = calculate_cost(&pricing, 200_000, 50_000, 0, 1_000_000);
+        assert_close(cost, 3.0 + 3.75 + 1.5);
+    }
+
+    #[test]
+    fn reasoning_bills_at_output_rate_unless_overridden() {
+        let pricing = pricing_for(DEFAULT_MODEL);
+        assert_close(reasoning_cost(&pricing, 1_000_000), 15.0);
+
+        let overridden = ModelPricing { reasoning: Some(10.0), ..pricing };
+        assert_close(reasoning_cost(&overridden, 1_000_000), 10.0);
+    }
+
+    #[test]
+    fn one_hour_cache_writes_bill_at_twice_input_unless_overridden() {
+        let pricing = pricing_for(DEFAULT_MODEL);
+        assert_close(cache_write_cost(&pricing, 1_000_000, 1_000_000), 3.75 + 6.0);
+
+        let overridden = ModelPricing { cache_write_1h: Some(5.0), ..pricing };
+        assert_close(cache_write_cost(&overridden, 0, 1_000_000), 5.0);
+    }
+
+    #[test]
+    fn lookup_handles_dotted_and_dated_names() {
+        assert_eq!(pricing_for("claude-sonnet-4.5"), SONNET);
+        assert_eq!(pricing_for("claude-opus-4-5-20251101"), OPUS_4_5);
+        assert_eq!(pricing_for("claude-haiku-4-5"), HAIKU_4_5);
+        assert_eq!(pricing_for("claude-3-5-haiku-20241022"), HAIKU_3_5);
+        assert_eq!(pricing_for("claude-3-haiku-20240307"), HAIKU_3);
+    }
+
+    #[test]
+    fn batch_tier_halves_cost_and_unknown_tiers_are_standard() {
+        let pricing = pricing_for(DEFAULT_MODEL);
+        let standard = calculate_cost(&pricing, 1_000_000, 1_000_000, 0, 0);
+
+        assert_close(calculate_tiered_cost(&pricing, ServiceTier::Batch, 1_000_000, 1_000_000, 0, 0), standard * 0.5);
+        assert_close(calculate_tiered_cost(&pricing, ServiceTier::Priority, 1_000_000, 1_000_000, 0, 0), standard);
+        assert_eq!(ServiceTier::from_name(Some("Batch")), ServiceTier::Batch);
+        assert_eq!(ServiceTier::from_name(Some("flex")), ServiceTier::Standard);
+        assert_eq!(ServiceTier::from_name(None), ServiceTier::Standard);
+    }
+
+    #[test]
+    fn unknown_model_falls_back_to_default() {
+        assert_eq!(pricing_for("some-future-model"), pricing_for(DEFAULT_MODEL));
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_built_ins() {
+        let custom = ModelPricing::new(1.0, 2.0, 3.0, 4.0);
+        let overrides = HashMap::from([(DEFAULT_MODEL.to_string(), custom)]);
+
+        assert_eq!(resolve_pricing(DEFAULT_MODEL, Some(&overrides)), custom);
+        assert_eq!(resolve_pricing("claude-opus-4-1", Some(&overrides)), OPUS);
+        assert_eq!(resolve_pricing(DEFAULT_MODEL, None), SONNET);
+    }
+
+    #[test]
+    fn cache_hit_ratio_and_savings() {
+        assert_eq!(cache_hit_ratio(0, 0, 0), None);
+        assert_close(cache_hit_ratio(100, 100, 800).unwrap(), 0.8);
+
+        // Sonnet: $3.00 input vs $0.30 cache read saves $2.70 per million
+        assert_close(cache_savings(&SONNET, 1_000_000), 2.70);
+        assert_close(cache_savings(&SONNET, 0), 0.0);
+    }
+
+    #[test]
+    fn zero_usage_costs_nothing() {
+        assert_close(calculate_cost(&pricing_for(DEFAULT_MODEL), 0, 0, 0, 0), 0.0);
+    }
+}
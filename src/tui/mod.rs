@@ -0,0 +1,278 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, LineGauge, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::display;
+use crate::local::{self, AggregatedUsage, BurnRate, LogWatcher, UsageFilter, UsageTracker};
+use crate::pricing::ModelPricing;
+use crate::util::{format_number, format_ratio};
+
+/// Usage data rendered in one TUI frame
+struct Snapshot {
+    total: AggregatedUsage,
+    /// Projects sorted by total tokens, highest first
+    projects: Vec<(String, AggregatedUsage)>,
+    /// Session being counted with `--session current`, once the logs name one
+    session: Option<String>,
+    updated_at: DateTime<Local>,
+}
+
+impl Snapshot {
+    fn load(tracker: &mut UsageTracker) -> Result<Self> {
+        tracker.refresh()?;
+        let by_project = tracker.by_project();
+
+        let mut total = AggregatedUsage::default();
+        for usage in by_project.values() {
+            total.merge(usage);
+        }
+
+        let mut projects: Vec<_> = by_project
+            .into_iter()
+            .map(|(name, usage)| (local::readable_project_name(&name), usage))
+            .collect();
+        projects.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.total()));
+
+        let session = tracker.session().map(str::to_string);
+        Ok(Self { total, projects, session, updated_at: Local::now() })
+    }
+}
+
+/// Settings shared by every frame
+struct View<'a> {
+    projects_dir: &'a Path,
+    filter: &'a UsageFilter,
+    pricing: &'a ModelPricing,
+    refresh: Duration,
+    /// When `--duration` runs out, if set
+    deadline: Option<Instant>,
+    /// Redraw on log changes instead of every `refresh`, if set
+    watcher: Option<&'a LogWatcher>,
+    /// Project directories skipped by `--exclude` when the view opened
+    excluded: usize,
+}
+
+/// Run the interactive live view until the user quits or `duration` passes
+/// Returns the last usage totals so the caller can print a final summary,
+/// or `None` when the terminal can't be taken over and the caller should render plainly
+pub fn run(
+    projects_dir: &Path,
+    refresh: Duration,
+    duration: Option<Duration>,
+    watcher: Option<&LogWatcher>,
+    filter: &UsageFilter,
+    pricing: &ModelPricing,
+) -> Result<Option<AggregatedUsage>> {
+    let excluded = local::excluded_projects(projects_dir, filter)?.len();
+    let deadline = duration.map(|duration| Instant::now() + duration);
+    let view = View { projects_dir, filter, pricing, refresh, deadline, watcher, excluded };
+
+    // Load once before taking over the screen so setup errors print normally
+    let mut tracker = UsageTracker::new(projects_dir, filter);
+    let snapshot = Snapshot::load(&mut tracker)?;
+
+    let mut terminal = match ratatui::try_init() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            // Undo whatever part of the setup did succeed
+            ratatui::restore();
+            tracing::warn!("Could not start the interactive view ({}); using the plain view instead", e);
+            return Ok(None);
+        }
+    };
+    let result = event_loop(&mut terminal, &view, &mut tracker, snapshot);
+    ratatui::restore();
+
+    result.map(Some)
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    view: &View,
+    tracker: &mut UsageTracker,
+    mut snapshot: Snapshot,
+) -> Result<AggregatedUsage> {
+    let mut table_state = TableState::default();
+    let mut last_refresh = Instant::now();
+    let mut burn_rate = BurnRate::default();
+    burn_rate.update(&snapshot.total, snapshot.total.estimated_cost(view.pricing));
+
+    loop {
+        if view.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(snapshot.total);
+        }
+
+        terminal.draw(|frame| draw(frame, view, &snapshot, &burn_rate, &mut table_state))?;
+
+        let mut timeout = match view.watcher {
+            Some(_) => local::WATCH_DEBOUNCE,
+            None => view.refresh.saturating_sub(last_refresh.elapsed()),
+        };
+        if let Some(deadline) = view.deadline {
+            timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(snapshot.total),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(snapshot.total);
+                    }
+                    KeyCode::Char('r') => {
+                        snapshot = Snapshot::load(tracker)?;
+                        burn_rate.update(&snapshot.total, snapshot.total.estimated_cost(view.pricing));
+                        last_refresh = Instant::now();
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => table_state.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => table_state.select_previous(),
+                    _ => {}
+                }
+            }
+        }
+
+        let due = match view.watcher {
+            Some(watcher) => watcher.changed(),
+            None => last_refresh.elapsed() >= view.refresh,
+        };
+        if due {
+            snapshot = Snapshot::load(tracker)?;
+            burn_rate.update(&snapshot.total, snapshot.total.estimated_cost(view.pricing));
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, view: &View, snapshot: &Snapshot, burn_rate: &BurnRate, table_state: &mut TableState) {
+    let [header, top, projects, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(7),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [tokens, cost] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+
+    draw_header(frame, header, view, snapshot.session.as_deref());
+    draw_token_gauges(frame, tokens, &snapshot.total);
+    draw_cost_panel(frame, cost, view, &snapshot.total, burn_rate);
+    draw_projects(frame, projects, view, snapshot, table_state);
+
+    let cadence = match view.watcher {
+        Some(_) => "watching for changes".to_string(),
+        None => format!("refreshing every {}s", view.refresh.as_secs()),
+    };
+    let mut help = format!(
+        " q quit · r refresh · ↑/↓ scroll · {} · updated {}",
+        cadence,
+        snapshot.updated_at.format("%H:%M:%S")
+    );
+    if let Some(deadline) = view.deadline {
+        help.push_str(&format!(" · stopping in {}s", deadline.saturating_duration_since(Instant::now()).as_secs()));
+    }
+    frame.render_widget(Paragraph::new(help).style(Style::default().fg(Color::DarkGray)), footer);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, view: &View, session: Option<&str>) {
+    let mut spans = vec![Span::styled(
+        "CLAUDE CODE - LIVE TOKEN USAGE",
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+    )];
+    if let Some(model) = &view.filter.model {
+        spans.push(Span::raw(format!("  (model filter: {})", model)));
+    }
+    if view.filter.exclude.is_some() {
+        spans.push(Span::raw(format!("  ({} project(s) excluded)", view.excluded)));
+    }
+    if view.filter.current_session {
+        match session {
+            Some(session) => spans.push(Span::raw(format!("  (session: {})", session))),
+            None => spans.push(Span::styled("  (no session IDs; all-time)", Style::default().fg(Color::Yellow))),
+        }
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Blue))
+        .title(format!(" {} ", view.projects_dir.display()));
+    frame.render_widget(Paragraph::new(Line::from(spans)).block(block), area);
+}
+
+fn draw_token_gauges(frame: &mut Frame, area: Rect, usage: &AggregatedUsage) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Tokens: {} ", format_number(usage.total())));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let parts = [
+        ("Input       ", usage.total_input, Color::Cyan),
+        ("Output      ", usage.total_output, Color::Yellow),
+        ("Cache write ", usage.total_cache_creation, Color::Magenta),
+        ("Cache read  ", usage.total_cache_read, Color::Green),
+    ];
+    let rows = Layout::vertical([Constraint::Length(1); 5]).split(inner);
+
+    for ((label, count, color), row) in parts.into_iter().zip(rows.iter()) {
+        let ratio = if usage.total() == 0 { 0.0 } else { count as f64 / usage.total() as f64 };
+        let gauge = LineGauge::default()
+            .filled_style(Style::default().fg(color))
+            .label(format!("{}{:>15}", label, format_number(count)))
+            .ratio(ratio);
+        frame.render_widget(gauge, *row);
+    }
+
+    let hit_ratio = format!("Cache hit ratio: {}", format_ratio(usage.cache_hit_ratio()));
+    frame.render_widget(Paragraph::new(hit_ratio), rows[4]);
+}
+
+fn draw_cost_panel(frame: &mut Frame, area: Rect, view: &View, usage: &AggregatedUsage, burn_rate: &BurnRate) {
+    let cost = usage.estimated_cost(view.pricing);
+    let per_message = if usage.message_count == 0 { 0.0 } else { cost / usage.message_count as f64 };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Total cost:     "),
+            Span::styled(display::format_cost(cost), Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(format!("Messages:       {}", format_number(usage.message_count as u64))),
+        Line::from(format!("Cost / message: {}", display::currency().format(per_message, 4))),
+        Line::from(format!("Cache savings:  {}", display::format_cost(usage.cache_savings(view.pricing)))),
+        Line::from(format!("Burn rate:      {}", burn_rate)),
+    ];
+
+    let block = Block::default().borders(Borders::ALL).title(" Estimated Cost ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_projects(frame: &mut Frame, area: Rect, view: &View, snapshot: &Snapshot, table_state: &mut TableState) {
+    let header = Row::new(["Project", "Total tokens", "Cost"])
+        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+
+    let rows = snapshot.projects.iter().map(|(name, usage)| {
+        Row::new([
+            name.clone(),
+            format_number(usage.total()),
+            display::format_cost(usage.estimated_cost(view.pricing)),
+        ])
+    });
+
+    let table = Table::new(rows, [Constraint::Min(20), Constraint::Length(16), Constraint::Length(12)])
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(format!(" Projects ({}) ", snapshot.projects.len())));
+
+    frame.render_stateful_widget(table, area, table_state);
+}
@@ -0,0 +1,218 @@
+use std::sync::OnceLock;
+
+/// How token counts are rendered for display
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    /// Inserted every three digits, e.g. "," (default), "." or " "
+    pub thousands_sep: String,
+    /// Show large counts as 1.2M or 345K instead of exact digits
+    pub abbreviate: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self { thousands_sep: ",".to_string(), abbreviate: false }
+    }
+}
+
+impl NumberFormat {
+    /// Raw digits, with no separators or abbreviation, for output read by other tools
+    pub fn plain() -> Self {
+        Self { thousands_sep: String::new(), abbreviate: false }
+    }
+
+    /// Format a count under this format
+    pub fn format(&self, n: u64) -> String {
+        if self.abbreviate {
+            self.abbreviated(n)
+        } else {
+            group_digits(&n.to_string(), &self.thousands_sep)
+        }
+    }
+
+    /// Round to one decimal of the largest fitting unit (K, M, B, T), or none under 100 of it
+    fn abbreviated(&self, n: u64) -> String {
+        const UNITS: [(u64, &str); 4] = [(1_000, "K"), (1_000_000, "M"), (1_000_000_000, "B"), (1_000_000_000_000, "T")];
+
+        let Some(mut index) = UNITS.iter().rposition(|&(size, _)| n >= size) else {
+            return n.to_string();
+        };
+        // Rounding can carry into the next unit (999,960 is 1M, not 1000K)
+        if index + 1 < UNITS.len() && n as f64 / UNITS[index].0 as f64 >= 999.95 {
+            index += 1;
+        }
+
+        let (size, suffix) = UNITS[index];
+        let value = n as f64 / size as f64;
+        let digits = if value >= 99.95 { format!("{:.0}", value) } else { format!("{:.1}", value) };
+        let digits = digits.strip_suffix(".0").unwrap_or(&digits);
+
+        // Use a decimal comma where the dot is taken as the thousands separator
+        let digits = if self.thousands_sep == "." { digits.replace('.', ",") } else { digits.to_string() };
+        format!("{}{}", digits, suffix)
+    }
+}
+
+/// Set from `--abbrev` and `thousands_sep` in the config file
+static NUMBER_FORMAT: OnceLock<NumberFormat> = OnceLock::new();
+
+/// Choose how counts are formatted for the rest of the process
+/// Only the first call takes effect; counts use commas until then
+pub fn set_number_format(format: NumberFormat) {
+    let _ = NUMBER_FORMAT.set(format);
+}
+
+/// The format counts are shown in
+pub fn number_format() -> &'static NumberFormat {
+    NUMBER_FORMAT.get_or_init(NumberFormat::default)
+}
+
+/// Format a number with thousand separators (or abbreviated, with `--abbrev`)
+pub fn format_number(n: u64) -> String {
+    number_format().format(n)
+}
+
+/// Format a signed number like `format_number`, keeping the minus sign in front
+pub fn format_signed_number(n: i64) -> String {
+    let formatted = format_number(n.unsigned_abs());
+    if n < 0 {
+        format!("-{}", formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Format an optional ratio as a percentage, or "—" when undefined
+pub fn format_ratio(ratio: Option<f64>) -> String {
+    match ratio {
+        Some(ratio) => format!("{:.1}%", ratio * 100.0),
+        None => "—".to_string(),
+    }
+}
+
+/// Currency that USD costs are converted to for display
+/// The exchange rate is supplied by the user; nothing is fetched live
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. "EUR"
+    pub code: String,
+    /// Units of this currency per US dollar
+    pub rate: f64,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self { code: "USD".to_string(), rate: 1.0 }
+    }
+}
+
+impl Currency {
+    /// Symbol printed before amounts, or the code itself for currencies without one here
+    pub fn symbol(&self) -> String {
+        match self.code.as_str() {
+            "USD" => "$".to_string(),
+            "EUR" => "€".to_string(),
+            "GBP" => "£".to_string(),
+            "JPY" | "CNY" => "¥".to_string(),
+            "INR" => "₹".to_string(),
+            code => format!("{} ", code),
+        }
+    }
+
+    /// Convert a USD amount and format it with this currency's symbol
+    pub fn format(&self, usd: f64, decimals: usize) -> String {
+        format!("{}{:.*}", self.symbol(), decimals, usd * self.rate)
+    }
+}
+
+/// Insert `sep` every three digits, counting from the right
+fn group_digits(digits: &str, sep: &str) -> String {
+    let mut result = String::new();
+    let mut count = 0;
+
+    for c in digits.chars().rev() {
+        if count == 3 {
+            result.insert_str(0, sep);
+            count = 0;
+        }
+        result.insert(0, c);
+        count += 1;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_zero() {
+        assert_eq!(format_number(0), "0");
+        assert_eq!(format_signed_number(0), "0");
+    }
+
+    #[test]
+    fn leaves_small_numbers_ungrouped() {
+        assert_eq!(format_number(7), "7");
+        assert_eq!(format_number(999), "999");
+    }
+
+    #[test]
+    fn groups_exact_multiples_of_a_thousand() {
+        assert_eq!(format_number(1_000), "1,000");
+        assert_eq!(format_number(1_000_000), "1,000,000");
+        assert_eq!(format_number(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn places_minus_sign_before_grouped_digits() {
+        assert_eq!(format_signed_number(-1), "-1");
+        assert_eq!(format_signed_number(-999), "-999");
+        assert_eq!(format_signed_number(-1_000), "-1,000");
+        assert_eq!(format_signed_number(-123_456_789), "-123,456,789");
+        assert_eq!(format_signed_number(i64::MIN), "-9,223,372,036,854,775,808");
+    }
+
+    #[test]
+    fn groups_with_a_configured_separator() {
+        let dots = NumberFormat { thousands_sep: ".".to_string(), abbreviate: false };
+        assert_eq!(dots.format(1_234_567), "1.234.567");
+
+        let spaces = NumberFormat { thousands_sep: " ".to_string(), abbreviate: false };
+        assert_eq!(spaces.format(12_345), "12 345");
+        assert_eq!(spaces.format(999), "999");
+    }
+
+    #[test]
+    fn plain_format_prints_raw_digits() {
+        assert_eq!(NumberFormat::plain().format(1_234_567), "1234567");
+        assert_eq!(NumberFormat::plain().format(0), "0");
+    }
+
+    #[test]
+    fn abbreviates_large_counts() {
+        let abbrev = NumberFormat { abbreviate: true, ..NumberFormat::default() };
+        assert_eq!(abbrev.format(999), "999");
+        assert_eq!(abbrev.format(1_000), "1K");
+        assert_eq!(abbrev.format(1_234), "1.2K");
+        assert_eq!(abbrev.format(345_000), "345K");
+        assert_eq!(abbrev.format(999_960), "1M");
+        assert_eq!(abbrev.format(1_260_000), "1.3M");
+        assert_eq!(abbrev.format(38_400_000_000), "38.4B");
+
+        let european = NumberFormat { thousands_sep: ".".to_string(), abbreviate: true };
+        assert_eq!(european.format(1_234_567), "1,2M");
+    }
+
+    #[test]
+    fn converts_and_formats_costs() {
+        assert_eq!(Currency::default().format(1.5, 2), "$1.50");
+
+        let eur = Currency { code: "EUR".to_string(), rate: 0.9 };
+        assert_eq!(eur.format(10.0, 2), "€9.00");
+
+        let chf = Currency { code: "CHF".to_string(), rate: 0.8 };
+        assert_eq!(chf.format(1.0, 4), "CHF 0.8000");
+    }
+}